@@ -0,0 +1,77 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Procedural macros
+
+use proc_macro::{TokenStream,TokenTree};
+
+// the vector type for each number of components
+const TYPES: [&str; 3] = ["XY","XYZ","XYZW"];
+const FIELDS: [&str; 4] = ["x","y","z","w"];
+
+// swizzle!(XYZ: x,y,z) generates a getter for every combination of 2 to 4 of
+// the components, and a setter for every combination of 2 up to all of them
+// where no component repeats.
+#[proc_macro]
+pub fn swizzle(input: TokenStream) -> TokenStream {
+    let mut v = None;
+    let mut components = Vec::new();
+    for token in input {
+        match token {
+            TokenTree::Ident(ident) => if v.is_none() {
+                v = Some(ident.to_string());
+            }
+            else {
+                components.push(ident.to_string());
+            },
+            TokenTree::Punct(ref p) if (p.as_char() == ':') || (p.as_char() == ',') => { },
+            _ => panic!("swizzle: expected `Type: component,component,...`"),
+        }
+    }
+    let v = v.expect("swizzle: missing vector type");
+    let mut code = format!("impl<T: Copy> {}<T> {{\n",v);
+    for n in 2..=4 {
+        for names in combinations(&components,n) {
+            code += &getter(&names);
+        }
+    }
+    for n in 2..=components.len() {
+        for names in combinations(&components,n) {
+            if !repeats(&names) {
+                code += &setter(&names);
+            }
+        }
+    }
+    code += "}\n";
+    code.parse().unwrap()
+}
+
+// all n-tuples of the components, in lexicographic order of the component list
+fn combinations(components: &[String],n: usize) -> Vec<Vec<String>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for c in components.iter() {
+        for mut rest in combinations(components,n - 1) {
+            rest.insert(0,c.clone());
+            result.push(rest);
+        }
+    }
+    result
+}
+
+fn repeats(names: &[String]) -> bool {
+    names.iter().enumerate().any(|(i,a)| names[i + 1..].contains(a))
+}
+
+fn getter(names: &[String]) -> String {
+    let t = TYPES[names.len() - 2];
+    let fields: Vec<String> = names.iter().zip(FIELDS.iter()).map(|(c,f)| format!("{}: self.{},",f,c)).collect();
+    format!("pub fn {}(self) -> {}<T> {{ {} {{ {} }} }}\n",names.concat(),t,t,fields.concat())
+}
+
+fn setter(names: &[String]) -> String {
+    let t = TYPES[names.len() - 2];
+    let fields: Vec<String> = names.iter().zip(FIELDS.iter()).map(|(c,f)| format!("self.{} = v.{};",c,f)).collect();
+    format!("pub fn set_{}(&mut self,v: {}<T>) {{ {} }}\n",names.concat(),t,fields.concat())
+}
@@ -6,6 +6,7 @@ use std::{cmp,fmt,ops};
 use crate::*;
 
 #[derive(Copy,Clone)]
+#[repr(C)]
 pub struct RGB<T> {
     pub r: T,
     pub g: T,
@@ -192,6 +193,7 @@ pub type f64rgb = RGB<f64>;
 impl_rgb!(f64);
 
 #[derive(Copy,Clone)]
+#[repr(C)]
 pub struct RGBA<T> {
     pub r: T,
     pub g: T,
@@ -0,0 +1,534 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// CIE XYZ, CIELAB, CIELCh, Oklab and Oklch
+
+use std::{cmp,fmt};
+use crate::*;
+
+// Linear RGB is linear sRGB, which is relative to D65. CIELAB is relative to
+// a white point given at conversion time, the From conversions use D50 (like
+// ICC profiles and CSS) with Bradford adaptation from D65. Oklab is relative to
+// D65 by definition. Lightness is 0..100 in CIELAB and 0..1 in Oklab, hues are
+// in degrees.
+
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum WhitePoint {
+    D50,
+    D65,
+}
+
+#[derive(Copy,Clone)]
+pub struct CIEXYZ<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct Lab<T> {
+    pub l: T,
+    pub a: T,
+    pub b: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct LCh<T> {
+    pub l: T,
+    pub c: T,
+    pub h: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct Oklab<T> {
+    pub l: T,
+    pub a: T,
+    pub b: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct Oklch<T> {
+    pub l: T,
+    pub c: T,
+    pub h: T,
+}
+
+macro_rules! impl_colorspace (
+    ($t:ty) => (
+        impl CIEXYZ<$t> {
+            pub fn new(x: $t,y: $t,z: $t) -> CIEXYZ<$t> {
+                CIEXYZ {
+                    x: x,
+                    y: y,
+                    z: z,
+                }
+            }
+
+            // the white point with Y = 1, from its CIE 1931 xy chromaticity
+            pub fn white(wp: WhitePoint) -> CIEXYZ<$t> {
+                let (x,y): ($t,$t) = match wp {
+                    WhitePoint::D50 => (0.3457,0.3585),
+                    WhitePoint::D65 => (0.3127,0.3290),
+                };
+                CIEXYZ {
+                    x: x / y,
+                    y: 1.0,
+                    z: (1.0 - x - y) / y,
+                }
+            }
+
+            // Bradford chromatic adaptation matrix
+            pub fn bradford(from: WhitePoint,to: WhitePoint) -> Mat3x3<$t> {
+                let m = Mat3x3::<$t>::new(
+                    XYZ::<$t>::new(0.8951,0.2664,-0.1614),
+                    XYZ::<$t>::new(-0.7502,1.7135,0.0367),
+                    XYZ::<$t>::new(0.0389,-0.0685,1.0296),
+                ).transpose();
+                let s = m * XYZ::<$t>::from(CIEXYZ::<$t>::white(from));
+                let d = m * XYZ::<$t>::from(CIEXYZ::<$t>::white(to));
+                m.inverse() * Mat3x3::<$t>::scale(XYZ::<$t>::new(d.x / s.x,d.y / s.y,d.z / s.z)) * m
+            }
+
+            pub fn adapt(&self,from: WhitePoint,to: WhitePoint) -> CIEXYZ<$t> {
+                if from == to {
+                    return *self;
+                }
+                CIEXYZ::<$t>::from(CIEXYZ::<$t>::bradford(from,to) * XYZ::<$t>::from(*self))
+            }
+
+            fn srgb_matrix() -> Mat3x3<$t> {
+                Mat3x3::<$t>::new(
+                    XYZ::<$t>::new(0.41239079926595934,0.357584339383878,0.1804807884018343),
+                    XYZ::<$t>::new(0.21263900587151027,0.715168678767756,0.07219231536073371),
+                    XYZ::<$t>::new(0.01933081871559182,0.11919477979462598,0.9505321522496607),
+                ).transpose()
+            }
+
+            fn srgb_inverse_matrix() -> Mat3x3<$t> {
+                Mat3x3::<$t>::new(
+                    XYZ::<$t>::new(3.2409699419045226,-1.537383177570094,-0.4986107602930034),
+                    XYZ::<$t>::new(-0.9692436362808796,1.8759675015077202,0.04155505740717559),
+                    XYZ::<$t>::new(0.05563007969699366,-0.20397695888897652,1.0569715142428786),
+                ).transpose()
+            }
+        }
+
+        impl From<CIEXYZ<$t>> for XYZ<$t> {
+            fn from(c: CIEXYZ<$t>) -> XYZ<$t> {
+                XYZ {
+                    x: c.x,
+                    y: c.y,
+                    z: c.z,
+                }
+            }
+        }
+
+        impl From<XYZ<$t>> for CIEXYZ<$t> {
+            fn from(v: XYZ<$t>) -> CIEXYZ<$t> {
+                CIEXYZ {
+                    x: v.x,
+                    y: v.y,
+                    z: v.z,
+                }
+            }
+        }
+
+        // D65
+        impl From<LinearRGB<$t>> for CIEXYZ<$t> {
+            fn from(c: LinearRGB<$t>) -> CIEXYZ<$t> {
+                let v = XYZ::<$t>::new(c.0.r,c.0.g,c.0.b);
+                CIEXYZ::<$t>::from(CIEXYZ::<$t>::srgb_matrix() * v)
+            }
+        }
+
+        impl From<CIEXYZ<$t>> for LinearRGB<$t> {
+            fn from(c: CIEXYZ<$t>) -> LinearRGB<$t> {
+                let v = CIEXYZ::<$t>::srgb_inverse_matrix() * XYZ::<$t>::from(c);
+                LinearRGB::<$t>::new(v.x,v.y,v.z)
+            }
+        }
+
+        impl Lab<$t> {
+            pub fn new(l: $t,a: $t,b: $t) -> Lab<$t> {
+                Lab {
+                    l: l,
+                    a: a,
+                    b: b,
+                }
+            }
+
+            const EPSILON: $t = 216.0 / 24389.0;
+            const KAPPA: $t = 24389.0 / 27.0;
+
+            pub fn from_xyz(c: &CIEXYZ<$t>,wp: WhitePoint) -> Lab<$t> {
+                let w = CIEXYZ::<$t>::white(wp);
+                let f = |t: $t| if t > Lab::<$t>::EPSILON {
+                    t.cbrt()
+                }
+                else {
+                    (Lab::<$t>::KAPPA * t + 16.0) / 116.0
+                };
+                let fx = f(c.x / w.x);
+                let fy = f(c.y / w.y);
+                let fz = f(c.z / w.z);
+                Lab {
+                    l: 116.0 * fy - 16.0,
+                    a: 500.0 * (fx - fy),
+                    b: 200.0 * (fy - fz),
+                }
+            }
+
+            pub fn to_xyz(&self,wp: WhitePoint) -> CIEXYZ<$t> {
+                let w = CIEXYZ::<$t>::white(wp);
+                let fy = (self.l + 16.0) / 116.0;
+                let fx = fy + self.a / 500.0;
+                let fz = fy - self.b / 200.0;
+                let finv = |f: $t| if f * f * f > Lab::<$t>::EPSILON {
+                    f * f * f
+                }
+                else {
+                    (116.0 * f - 16.0) / Lab::<$t>::KAPPA
+                };
+                let y = if self.l > Lab::<$t>::KAPPA * Lab::<$t>::EPSILON {
+                    fy * fy * fy
+                }
+                else {
+                    self.l / Lab::<$t>::KAPPA
+                };
+                CIEXYZ {
+                    x: finv(fx) * w.x,
+                    y: y * w.y,
+                    z: finv(fz) * w.z,
+                }
+            }
+
+            // plain euclidean distance
+            pub fn delta_e76(&self,other: &Lab<$t>) -> $t {
+                let dl = self.l - other.l;
+                let da = self.a - other.a;
+                let db = self.b - other.b;
+                (dl * dl + da * da + db * db).sqrt()
+            }
+
+            // graphic arts weights (kL = 1, K1 = 0.045, K2 = 0.015), self is the reference
+            pub fn delta_e94(&self,other: &Lab<$t>) -> $t {
+                let c1 = self.a.hypot(self.b);
+                let c2 = other.a.hypot(other.b);
+                let dl = self.l - other.l;
+                let dc = c1 - c2;
+                let da = self.a - other.a;
+                let db = self.b - other.b;
+                let dh2 = (da * da + db * db - dc * dc).max(0.0);
+                let sc = 1.0 + 0.045 * c1;
+                let sh = 1.0 + 0.015 * c1;
+                (dl * dl + (dc / sc) * (dc / sc) + dh2 / (sh * sh)).sqrt()
+            }
+
+            // CIEDE2000 with kL = kC = kH = 1, following Sharma, Wu and Dalal
+            pub fn delta_e2000(&self,other: &Lab<$t>) -> $t {
+                let rad = |d: $t| d.to_radians();
+                let c1 = self.a.hypot(self.b);
+                let c2 = other.a.hypot(other.b);
+                let cm = 0.5 * (c1 + c2);
+                let cm7 = cm.powi(7);
+                let g = 0.5 * (1.0 - (cm7 / (cm7 + (25.0 as $t).powi(7))).sqrt());
+                let a1 = (1.0 + g) * self.a;
+                let a2 = (1.0 + g) * other.a;
+                let c1 = a1.hypot(self.b);
+                let c2 = a2.hypot(other.b);
+                let h = |b: $t,a: $t| if (a == 0.0) && (b == 0.0) {
+                    0.0
+                }
+                else {
+                    HSV::<$t>::wrap_hue(b.atan2(a).to_degrees())
+                };
+                let h1 = h(self.b,a1);
+                let h2 = h(other.b,a2);
+                let dl = other.l - self.l;
+                let dc = c2 - c1;
+                let dh = if c1 * c2 == 0.0 {
+                    0.0
+                }
+                else if (h2 - h1).abs() <= 180.0 {
+                    h2 - h1
+                }
+                else if h2 - h1 > 180.0 {
+                    h2 - h1 - 360.0
+                }
+                else {
+                    h2 - h1 + 360.0
+                };
+                let dh = 2.0 * (c1 * c2).sqrt() * rad(0.5 * dh).sin();
+                let lm = 0.5 * (self.l + other.l);
+                let cm = 0.5 * (c1 + c2);
+                let hm = if c1 * c2 == 0.0 {
+                    h1 + h2
+                }
+                else if (h1 - h2).abs() <= 180.0 {
+                    0.5 * (h1 + h2)
+                }
+                else if h1 + h2 < 360.0 {
+                    0.5 * (h1 + h2 + 360.0)
+                }
+                else {
+                    0.5 * (h1 + h2 - 360.0)
+                };
+                let t = 1.0 - 0.17 * rad(hm - 30.0).cos() + 0.24 * rad(2.0 * hm).cos() + 0.32 * rad(3.0 * hm + 6.0).cos() - 0.20 * rad(4.0 * hm - 63.0).cos();
+                let dtheta = 30.0 * (-((hm - 275.0) / 25.0).powi(2)).exp();
+                let cm7 = cm.powi(7);
+                let rc = 2.0 * (cm7 / (cm7 + (25.0 as $t).powi(7))).sqrt();
+                let lm50 = (lm - 50.0) * (lm - 50.0);
+                let sl = 1.0 + 0.015 * lm50 / (20.0 + lm50).sqrt();
+                let sc = 1.0 + 0.045 * cm;
+                let sh = 1.0 + 0.015 * cm * t;
+                let rt = -rad(2.0 * dtheta).sin() * rc;
+                let tl = dl / sl;
+                let tc = dc / sc;
+                let th = dh / sh;
+                (tl * tl + tc * tc + th * th + rt * tc * th).sqrt()
+            }
+        }
+
+        impl From<LinearRGB<$t>> for Lab<$t> {
+            fn from(c: LinearRGB<$t>) -> Lab<$t> {
+                let xyz = CIEXYZ::<$t>::from(c).adapt(WhitePoint::D65,WhitePoint::D50);
+                Lab::<$t>::from_xyz(&xyz,WhitePoint::D50)
+            }
+        }
+
+        impl From<Lab<$t>> for LinearRGB<$t> {
+            fn from(c: Lab<$t>) -> LinearRGB<$t> {
+                LinearRGB::<$t>::from(c.to_xyz(WhitePoint::D50).adapt(WhitePoint::D50,WhitePoint::D65))
+            }
+        }
+
+        impl Oklab<$t> {
+            pub fn new(l: $t,a: $t,b: $t) -> Oklab<$t> {
+                Oklab {
+                    l: l,
+                    a: a,
+                    b: b,
+                }
+            }
+
+            // euclidean distance, the usual difference for Oklab
+            pub fn delta_e(&self,other: &Oklab<$t>) -> $t {
+                let dl = self.l - other.l;
+                let da = self.a - other.a;
+                let db = self.b - other.b;
+                (dl * dl + da * da + db * db).sqrt()
+            }
+        }
+
+        impl From<LinearRGB<$t>> for Oklab<$t> {
+            fn from(c: LinearRGB<$t>) -> Oklab<$t> {
+                let (r,g,b) = (c.0.r,c.0.g,c.0.b);
+                let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+                let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+                let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+                Oklab {
+                    l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+                    a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+                    b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+                }
+            }
+        }
+
+        impl From<Oklab<$t>> for LinearRGB<$t> {
+            fn from(c: Oklab<$t>) -> LinearRGB<$t> {
+                let l = c.l + 0.3963377774 * c.a + 0.2158037573 * c.b;
+                let m = c.l - 0.1055613458 * c.a - 0.0638541728 * c.b;
+                let s = c.l - 0.0894841775 * c.a - 1.2914855480 * c.b;
+                let l = l * l * l;
+                let m = m * m * m;
+                let s = s * s * s;
+                LinearRGB::<$t>::new(
+                    4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+                    -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+                    -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+                )
+            }
+        }
+
+        impl_colorspace_polar!(Lab,LCh,$t);
+        impl_colorspace_polar!(Oklab,Oklch,$t);
+
+        impl_colorspace_common!(CIEXYZ,x,y,z,$t);
+        impl_colorspace_common!(Lab,l,a,b,$t);
+        impl_colorspace_common!(LCh,l,c,h,$t);
+        impl_colorspace_common!(Oklab,l,a,b,$t);
+        impl_colorspace_common!(Oklch,l,c,h,$t);
+    );
+);
+
+// cylindrical form of a lab space, chroma and hue in degrees
+macro_rules! impl_colorspace_polar (
+    ($lab:ident,$lch:ident,$t:ty) => (
+        impl $lch<$t> {
+            pub fn new(l: $t,c: $t,h: $t) -> $lch<$t> {
+                $lch {
+                    l: l,
+                    c: c,
+                    h: HSV::<$t>::wrap_hue(h),
+                }
+            }
+
+            pub fn lerp(&self,other: &$lch<$t>,t: $t,dir: HueDirection) -> $lch<$t> {
+                $lch {
+                    l: self.l + (other.l - self.l) * t,
+                    c: self.c + (other.c - self.c) * t,
+                    h: HSV::<$t>::lerp_hue(self.h,other.h,t,dir),
+                }
+            }
+        }
+
+        impl From<$lab<$t>> for $lch<$t> {
+            fn from(c: $lab<$t>) -> $lch<$t> {
+                $lch {
+                    l: c.l,
+                    c: c.a.hypot(c.b),
+                    h: HSV::<$t>::wrap_hue(c.b.atan2(c.a).to_degrees()),
+                }
+            }
+        }
+
+        impl From<$lch<$t>> for $lab<$t> {
+            fn from(c: $lch<$t>) -> $lab<$t> {
+                let (s,co) = c.h.to_radians().sin_cos();
+                $lab {
+                    l: c.l,
+                    a: c.c * co,
+                    b: c.c * s,
+                }
+            }
+        }
+
+        impl From<LinearRGB<$t>> for $lch<$t> {
+            fn from(c: LinearRGB<$t>) -> $lch<$t> {
+                $lch::<$t>::from($lab::<$t>::from(c))
+            }
+        }
+
+        impl From<$lch<$t>> for LinearRGB<$t> {
+            fn from(c: $lch<$t>) -> LinearRGB<$t> {
+                LinearRGB::<$t>::from($lab::<$t>::from(c))
+            }
+        }
+    );
+);
+
+macro_rules! impl_colorspace_common (
+    ($n:ident,$a:ident,$b:ident,$c:ident,$t:ty) => (
+        impl cmp::PartialEq for $n<$t> {
+            fn eq(&self,other: &$n<$t>) -> bool {
+                (self.$a == other.$a)
+                && (self.$b == other.$b)
+                && (self.$c == other.$c)
+            }
+        }
+
+        impl fmt::Display for $n<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.$a,self.$b,self.$c)
+            }
+        }
+
+        impl fmt::Debug for $n<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.$a,self.$b,self.$c)
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type f32ciexyz = CIEXYZ<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32lab = Lab<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32lch = LCh<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32oklab = Oklab<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32oklch = Oklch<f32>;
+
+impl_colorspace!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64ciexyz = CIEXYZ<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64lab = Lab<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64lch = LCh<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64oklab = Oklab<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64oklch = Oklch<f64>;
+
+impl_colorspace!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sharma, Wu and Dalal, "The CIEDE2000 color-difference formula:
+    // implementation notes, supplementary test data, and mathematical
+    // observations", Table 1
+    const SHARMA: [[f64; 7]; 34] = [
+        [50.0000,2.6772,-79.7751,50.0000,0.0000,-82.7485,2.0425],
+        [50.0000,3.1571,-77.2803,50.0000,0.0000,-82.7485,2.8615],
+        [50.0000,2.8361,-74.0200,50.0000,0.0000,-82.7485,3.4412],
+        [50.0000,-1.3802,-84.2814,50.0000,0.0000,-82.7485,1.0000],
+        [50.0000,-1.1848,-84.8006,50.0000,0.0000,-82.7485,1.0000],
+        [50.0000,-0.9009,-85.5211,50.0000,0.0000,-82.7485,1.0000],
+        [50.0000,0.0000,0.0000,50.0000,-1.0000,2.0000,2.3669],
+        [50.0000,-1.0000,2.0000,50.0000,0.0000,0.0000,2.3669],
+        [50.0000,2.4900,-0.0010,50.0000,-2.4900,0.0009,7.1792],
+        [50.0000,2.4900,-0.0010,50.0000,-2.4900,0.0010,7.1792],
+        [50.0000,2.4900,-0.0010,50.0000,-2.4900,0.0011,7.2195],
+        [50.0000,2.4900,-0.0010,50.0000,-2.4900,0.0012,7.2195],
+        [50.0000,-0.0010,2.4900,50.0000,0.0009,-2.4900,4.8045],
+        [50.0000,-0.0010,2.4900,50.0000,0.0010,-2.4900,4.8045],
+        [50.0000,-0.0010,2.4900,50.0000,0.0011,-2.4900,4.7461],
+        [50.0000,2.5000,0.0000,50.0000,0.0000,-2.5000,4.3065],
+        [50.0000,2.5000,0.0000,73.0000,25.0000,-18.0000,27.1492],
+        [50.0000,2.5000,0.0000,61.0000,-5.0000,29.0000,22.8977],
+        [50.0000,2.5000,0.0000,56.0000,-27.0000,-3.0000,31.9030],
+        [50.0000,2.5000,0.0000,58.0000,24.0000,15.0000,19.4535],
+        [50.0000,2.5000,0.0000,50.0000,3.1736,0.5854,1.0000],
+        [50.0000,2.5000,0.0000,50.0000,3.2972,0.0000,1.0000],
+        [50.0000,2.5000,0.0000,50.0000,1.8634,0.5757,1.0000],
+        [50.0000,2.5000,0.0000,50.0000,3.2592,0.3350,1.0000],
+        [60.2574,-34.0099,36.2677,60.4626,-34.1751,39.4387,1.2644],
+        [63.0109,-31.0961,-5.8663,62.8187,-29.7946,-4.0864,1.2630],
+        [61.2901,3.7196,-5.3901,61.4292,2.2480,-4.9620,1.8731],
+        [35.0831,-44.1164,3.7933,35.0232,-40.0716,1.5901,1.8645],
+        [22.7233,20.0904,-46.6940,23.0331,14.9730,-42.5619,2.0373],
+        [36.4612,47.8580,18.3852,36.2715,50.5065,21.2231,1.4146],
+        [90.8027,-2.0831,1.4410,91.1528,-1.6435,0.0447,1.4441],
+        [90.9257,-0.5406,-0.9208,88.6381,-0.8985,-0.7239,1.5381],
+        [6.7747,-0.2908,-2.4247,5.8714,-0.0985,-2.2286,0.6377],
+        [2.0776,0.0795,-1.1350,0.9033,-0.0636,-0.5514,0.9082],
+    ];
+
+    #[test]
+    fn delta_e2000_sharma() {
+        for row in SHARMA.iter() {
+            let a = f64lab::new(row[0],row[1],row[2]);
+            let b = f64lab::new(row[3],row[4],row[5]);
+            // the table is rounded to 4 decimals, and the formula is symmetric
+            assert!((a.delta_e2000(&b) - row[6]).abs() < 5e-5,"{:?}: {}",row,a.delta_e2000(&b));
+            assert!((b.delta_e2000(&a) - row[6]).abs() < 5e-5,"{:?}: {}",row,b.delta_e2000(&a));
+            let a = f32lab::new(row[0] as f32,row[1] as f32,row[2] as f32);
+            let b = f32lab::new(row[3] as f32,row[4] as f32,row[5] as f32);
+            assert!((a.delta_e2000(&b) - row[6] as f32).abs() < 1e-3,"{:?}: {}",row,a.delta_e2000(&b));
+        }
+    }
+}
@@ -32,8 +32,188 @@ macro_rules! impl_complex (
                 (self.r * self.r + self.i * self.i).sqrt()
             }
 
+            pub fn i() -> Complex<$t> {
+                Complex {
+                    r: 0.0,
+                    i: 1.0,
+                }
+            }
+
+            pub fn from_polar(r: $t,theta: $t) -> Complex<$t> {
+                Complex {
+                    r: r * theta.cos(),
+                    i: r * theta.sin(),
+                }
+            }
+
+            pub fn to_polar(self) -> ($t,$t) {
+                (self.abs(),self.arg())
+            }
+
+            pub fn norm_sqr(self) -> $t {
+                self.r * self.r + self.i * self.i
+            }
+
+            // principal value, in (-pi,pi]
             pub fn arg(self) -> $t {
-                self.r.atan2(self.i)
+                self.i.atan2(self.r)
+            }
+
+            pub fn recip(self) -> Complex<$t> {
+                1.0 / self
+            }
+
+            pub fn exp(self) -> Complex<$t> {
+                Complex::<$t>::from_polar(self.r.exp(),self.i)
+            }
+
+            // principal branch, cut along the negative real axis
+            pub fn ln(self) -> Complex<$t> {
+                Complex {
+                    r: self.abs().ln(),
+                    i: self.arg(),
+                }
+            }
+
+            // principal branch, real part is never negative
+            pub fn sqrt(self) -> Complex<$t> {
+                if (self.r == 0.0) && (self.i == 0.0) {
+                    return Complex::zero();
+                }
+                let t = (0.5 * (self.abs() + self.r.abs())).sqrt();
+                if self.r >= 0.0 {
+                    Complex {
+                        r: t,
+                        i: 0.5 * self.i / t,
+                    }
+                }
+                else {
+                    Complex {
+                        r: 0.5 * self.i.abs() / t,
+                        i: t.copysign(self.i),
+                    }
+                }
+            }
+
+            pub fn powf(self,e: $t) -> Complex<$t> {
+                if (self.r == 0.0) && (self.i == 0.0) {
+                    if e == 0.0 {
+                        Complex::one()
+                    }
+                    else {
+                        Complex::zero()
+                    }
+                }
+                else {
+                    Complex::<$t>::from_polar(self.abs().powf(e),self.arg() * e)
+                }
+            }
+
+            pub fn powc(self,e: Complex<$t>) -> Complex<$t> {
+                if (self.r == 0.0) && (self.i == 0.0) {
+                    if (e.r == 0.0) && (e.i == 0.0) {
+                        Complex::one()
+                    }
+                    else {
+                        Complex::zero()
+                    }
+                }
+                else {
+                    (e * self.ln()).exp()
+                }
+            }
+
+            pub fn sin(self) -> Complex<$t> {
+                Complex {
+                    r: self.r.sin() * self.i.cosh(),
+                    i: self.r.cos() * self.i.sinh(),
+                }
+            }
+
+            pub fn cos(self) -> Complex<$t> {
+                Complex {
+                    r: self.r.cos() * self.i.cosh(),
+                    i: -self.r.sin() * self.i.sinh(),
+                }
+            }
+
+            pub fn tan(self) -> Complex<$t> {
+                let r2 = 2.0 * self.r;
+                let i2 = 2.0 * self.i;
+                let d = r2.cos() + i2.cosh();
+                Complex {
+                    r: r2.sin() / d,
+                    i: i2.sinh() / d,
+                }
+            }
+
+            pub fn sinh(self) -> Complex<$t> {
+                Complex {
+                    r: self.r.sinh() * self.i.cos(),
+                    i: self.r.cosh() * self.i.sin(),
+                }
+            }
+
+            pub fn cosh(self) -> Complex<$t> {
+                Complex {
+                    r: self.r.cosh() * self.i.cos(),
+                    i: self.r.sinh() * self.i.sin(),
+                }
+            }
+
+            pub fn tanh(self) -> Complex<$t> {
+                let r2 = 2.0 * self.r;
+                let i2 = 2.0 * self.i;
+                let d = r2.cosh() + i2.cos();
+                Complex {
+                    r: r2.sinh() / d,
+                    i: i2.sin() / d,
+                }
+            }
+
+            // asin(z) = -i ln(iz + sqrt(1 - z^2)), cuts along the real axis outside [-1,1]
+            pub fn asin(self) -> Complex<$t> {
+                let iz = Complex::<$t>::new(-self.i,self.r);
+                let l = (iz + (1.0 - self * self).sqrt()).ln();
+                Complex {
+                    r: l.i,
+                    i: -l.r,
+                }
+            }
+
+            // acos(z) = -i ln(z + i sqrt(1 - z^2)), cuts along the real axis outside [-1,1]
+            pub fn acos(self) -> Complex<$t> {
+                let s = (1.0 - self * self).sqrt();
+                let l = (self + Complex::<$t>::new(-s.i,s.r)).ln();
+                Complex {
+                    r: l.i,
+                    i: -l.r,
+                }
+            }
+
+            // atan(z) = i/2 (ln(1 - iz) - ln(1 + iz)), cuts along the imaginary axis outside [-i,i]
+            pub fn atan(self) -> Complex<$t> {
+                let iz = Complex::<$t>::new(-self.i,self.r);
+                let l = (1.0 - iz).ln() - (1.0 + iz).ln();
+                Complex {
+                    r: -0.5 * l.i,
+                    i: 0.5 * l.r,
+                }
+            }
+
+            // asinh(z) = ln(z + sqrt(z^2 + 1)), cuts along the imaginary axis outside [-i,i]
+            pub fn asinh(self) -> Complex<$t> {
+                (self + (self * self + 1.0).sqrt()).ln()
+            }
+
+            // acosh(z) = ln(z + sqrt(z + 1) sqrt(z - 1)), cut along the real axis below 1
+            pub fn acosh(self) -> Complex<$t> {
+                (self + (self + 1.0).sqrt() * (self - 1.0).sqrt()).ln()
+            }
+
+            // atanh(z) = (ln(1 + z) - ln(1 - z)) / 2, cuts along the real axis outside [-1,1]
+            pub fn atanh(self) -> Complex<$t> {
+                ((1.0 + self).ln() - (1.0 - self).ln()) * 0.5
             }
         }
 
@@ -169,14 +349,14 @@ macro_rules! impl_complex (
 
         impl ops::SubAssign<$t> for Complex<$t> {
             fn sub_assign(&mut self,other: $t) {
-                self.r += other;
+                self.r -= other;
             }
         }
 
         impl ops::SubAssign<Complex<$t>> for Complex<$t> {
             fn sub_assign(&mut self,other: Complex<$t>) {
-                self.r += other.r;
-                self.i += other.i;
+                self.r -= other.r;
+                self.i -= other.i;
             }
         }
 
@@ -205,7 +385,7 @@ macro_rules! impl_complex (
             fn mul(self,other: Complex<$t>) -> Self::Output {
                 Complex {
                     r: self.r * other.r - self.i * other.i,
-                    i: self.r * other.i - self.i * other.r,
+                    i: self.r * other.i + self.i * other.r,
                 }
             }
         }
@@ -220,7 +400,7 @@ macro_rules! impl_complex (
         impl ops::MulAssign<Complex<$t>> for Complex<$t> {
             fn mul_assign(&mut self,other: Complex<$t>) {
                 let nr = self.r * other.r - self.i * other.i;
-                let ni = self.r * other.i - self.i * other.r;
+                let ni = self.r * other.i + self.i * other.r;
                 self.r = nr;
                 self.i = ni;
             }
@@ -248,7 +428,7 @@ macro_rules! impl_complex (
                 if d != 0.0 {
                     Complex {
                         r: self * other.r / d,
-                        i: self * other.i / d,
+                        i: -self * other.i / d,
                     }
                 }
                 else {
@@ -267,7 +447,7 @@ macro_rules! impl_complex (
                 if d != 0.0 {
                     Complex {
                         r: (self.r * other.r + self.i * other.i) / d,
-                        i: (self.r * other.i + self.i * other.r) / d,
+                        i: (self.i * other.r - self.r * other.i) / d,
                     }
                 }
                 else {
@@ -290,7 +470,7 @@ macro_rules! impl_complex (
                 let d = other.r * other.r + other.i * other.i;
                 if d != 0.0 {
                     let nr = (self.r * other.r + self.i * other.i) / d;
-                    let ni = (self.r * other.i + self.i * other.r) / d;
+                    let ni = (self.i * other.r - self.r * other.i) / d;
                     self.r = nr;
                     self.i = ni;
                 }
@@ -308,3 +488,92 @@ impl_complex!(f32);
 pub type f64c = Complex<f64>;
 
 impl_complex!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64c,b: f64c) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn arg_is_atan2_of_i_and_r() {
+        assert_eq!(f64c::new(1.0,0.0).arg(),0.0);
+        assert!((f64c::new(0.0,1.0).arg() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!((f64c::new(-1.0,0.0).arg() - std::f64::consts::PI).abs() < 1e-12);
+        assert!((f64c::new(1.0,-1.0).arg() + std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn mul() {
+        let a = f64c::new(1.0,2.0);
+        let b = f64c::new(3.0,4.0);
+        assert!(close(a * b,f64c::new(-5.0,10.0)));
+        let mut c = a;
+        c *= b;
+        assert!(close(c,f64c::new(-5.0,10.0)));
+    }
+
+    #[test]
+    fn div() {
+        let a = f64c::new(-5.0,10.0);
+        let b = f64c::new(3.0,4.0);
+        assert!(close(a / b,f64c::new(1.0,2.0)));
+        let mut c = a;
+        c /= b;
+        assert!(close(c,f64c::new(1.0,2.0)));
+        assert!(close(2.0 / f64c::new(1.0,1.0),f64c::new(1.0,-1.0)));
+    }
+
+    fn near(a: f64c,b: f64c) -> bool {
+        (a - b).abs() < 1e-12 * (1.0 + b.abs())
+    }
+
+    type Case = (fn(f64c) -> f64c,[(f64,f64); 4]);
+
+    // reference values from Python's cmath, at points away from the branch cuts
+    #[test]
+    fn known_values() {
+        let z = [f64c::new(0.5,0.25),f64c::new(-1.5,0.75),f64c::new(2.0,-0.5),f64c::new(-0.3,-1.7)];
+        let cases: [Case; 15] = [
+            (f64c::exp,[(1.5974665191199127,0.4079001700783598),(0.16326185449063912,0.15209416568738365),(6.484506781251244,-3.5425022000064983),(-0.09545034900864631,-0.7346433603920708)]),
+            (f64c::ln,[(-0.5815754049028404,0.4636476090008061),(0.5170368837652692,2.677945044588987),(0.7234594914681627,-0.24497866312686414),(0.5459616502586565,-1.7454685258031364)]),
+            (f64c::sqrt,[(0.7276733451126774,0.17178037486125622),(0.2975323370029233,1.2603672050487593),(1.425053124063947,-0.17543205637629383),(0.8444725129224773,-1.0065454908157918)]),
+            (f64c::sin,[(0.494485780933195,0.22168816414957482),(-1.2914400857052224,0.058168384501671194),(1.0253473885839877,0.21685216292078974),(-0.8358243686191043,-2.527468723190649)]),
+            (f64c::cos,[(0.9051501505596068,-0.12110879604381165),(0.09158227260398998,0.8202568175066206),(-0.4692579782290534,0.473830620416407),(2.701992959680282,-0.7818376958374182)]),
+            (f64c::tan,[(0.504500702698564,0.31242069250258875),(-0.10358061868779707,1.5628689818694739),(-0.8508781211449379,-1.3212865837711916),(-0.035682500915865334,-0.9457340139741869)]),
+            (f64c::sinh,[(0.504895714387995,0.2789791283502615),(-1.5579700760145927,1.6034935732013755),(3.1828694483371494,-1.8036926955321817),(0.039235763211921855,-1.0366254194722615)]),
+            (f64c::cosh,[(1.0925708047319176,0.12892104172809826),(1.7212319305052317,-1.4513994075139918),(3.3016373329140944,-1.7388095044743164),(-0.13468611222056817,0.30198205908019077)]),
+            (f64c::tanh,[(0.4854872810241353,0.19805544995134958),(-0.9881121005704792,0.09838781913967143),(0.979940849961738,-0.030215987322877582),(-2.9115207481259047,1.1686311699363665)]),
+            (f64c::asin,[(0.5016088532755008,0.28139605624529274),(-1.0242880572034125,1.1627920981631037),(1.2930420702371825,-1.3618009008578458),(-0.1513854812624992,-1.3107496902488216)]),
+            (f64c::acos,[(1.069187473519396,-0.28139605624529274),(2.595084383998309,-1.1627920981631037),(0.27775425655771396,1.3618009008578458),(1.7221818080573958,1.3107496902488216)]),
+            (f64c::atan,[(0.4842544903299662,0.20058661813123432),(-1.05713696075397,0.20793333596152302),(1.1265564408348223,-0.09641562020299617),(-1.4236790442393028,-0.6358752035135133)]),
+            (f64c::asinh,[(0.4926756834207706,0.2243284526346675),(-1.2650750428933684,0.4029108301465699),(1.4657153519472905,-0.22101863562288385),(-1.1511827117610314,-1.3583551589067286)]),
+            (f64c::acosh,[(0.28139605624529274,1.069187473519396),(1.1627920981631037,2.595084383998309),(1.3618009008578458,-0.27775425655771396),(1.3107496902488216,-1.7221818080573958)]),
+            (f64c::atanh,[(0.5003700000525311,0.3143981432077165),(-0.5315996311919018,1.2251278624101656),(0.5003700000525311,-1.421546861001807),(-0.07595582215779155,-1.049069489395666)]),
+        ];
+        for (f,expected) in cases.iter() {
+            for (z,e) in z.iter().zip(expected.iter()) {
+                assert!(near(f(*z),f64c::new(e.0,e.1)),"{:?} {:?}",z,e);
+            }
+        }
+    }
+
+    #[test]
+    fn principal_values() {
+        let pi = std::f64::consts::PI;
+        assert!(near(f64c::new(0.0,pi).exp(),f64c::new(-1.0,0.0)));
+        assert!(near(f64c::new(-1.0,0.0).ln(),f64c::new(0.0,pi)));
+        assert!(near(f64c::i().ln(),f64c::new(0.0,0.5 * pi)));
+        assert!(near(f64c::new(-4.0,0.0).sqrt(),f64c::new(0.0,2.0)));
+        assert!(near(f64c::new(3.0,4.0).sqrt(),f64c::new(2.0,1.0)));
+        assert!(near(f64c::new(-3.0,-4.0).sqrt(),f64c::new(1.0,-2.0)));
+        assert!(near(f64c::i().powc(f64c::i()),f64c::new((-0.5 * pi).exp(),0.0)));
+        assert!(near(f64c::new(1.0,2.0).powc(f64c::new(0.5,-1.0)),f64c::new(4.382565059863358,-1.1243974773611554)));
+        assert!(near(f64c::new(1.0,1.0).powf(2.0),f64c::new(0.0,2.0)));
+        assert!(near(f64c::new(2.0,0.0).asin(),f64c::new(0.5 * pi,1.3169578969248166)));
+        assert!(near(f64c::new(0.0,2.0).atan(),f64c::new(0.5 * pi,0.5493061443340549)));
+        assert!(near(f64c::new(-2.0,0.0).acosh(),f64c::new(1.3169578969248166,pi)));
+    }
+}
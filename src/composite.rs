@@ -0,0 +1,365 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Alpha compositing and blend modes
+
+use crate::*;
+
+// Porter-Duff operators as named in the W3C Compositing and Blending spec,
+// source is self and destination (backdrop) is the other color
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum CompositeOp {
+    Clear,
+    Copy,
+    Destination,
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+}
+
+// W3C blend modes, the first twelve are separable
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+macro_rules! impl_composite (
+    ($t:ty) => (
+        impl RGB<$t> {
+            fn lum(&self) -> $t {
+                0.3 * self.r + 0.59 * self.g + 0.11 * self.b
+            }
+
+            fn clip_color(&self) -> RGB<$t> {
+                let l = self.lum();
+                let n = self.r.min(self.g).min(self.b);
+                let x = self.r.max(self.g).max(self.b);
+                let mut c = *self;
+                if n < 0.0 {
+                    c = RGB {
+                        r: l + (c.r - l) * l / (l - n),
+                        g: l + (c.g - l) * l / (l - n),
+                        b: l + (c.b - l) * l / (l - n),
+                    };
+                }
+                if x > 1.0 {
+                    c = RGB {
+                        r: l + (c.r - l) * (1.0 - l) / (x - l),
+                        g: l + (c.g - l) * (1.0 - l) / (x - l),
+                        b: l + (c.b - l) * (1.0 - l) / (x - l),
+                    };
+                }
+                c
+            }
+
+            fn set_lum(&self,l: $t) -> RGB<$t> {
+                let d = l - self.lum();
+                RGB {
+                    r: self.r + d,
+                    g: self.g + d,
+                    b: self.b + d,
+                }.clip_color()
+            }
+
+            fn sat(&self) -> $t {
+                self.r.max(self.g).max(self.b) - self.r.min(self.g).min(self.b)
+            }
+
+            fn set_sat(&self,s: $t) -> RGB<$t> {
+                let mut c = [self.r,self.g,self.b];
+                let mut i = [0,1,2];
+                i.sort_by(|p,q| c[*p].total_cmp(&c[*q]));
+                let (min,mid,max) = (i[0],i[1],i[2]);
+                if c[max] > c[min] {
+                    c[mid] = (c[mid] - c[min]) * s / (c[max] - c[min]);
+                    c[max] = s;
+                }
+                else {
+                    c[mid] = 0.0;
+                    c[max] = 0.0;
+                }
+                c[min] = 0.0;
+                RGB {
+                    r: c[0],
+                    g: c[1],
+                    b: c[2],
+                }
+            }
+
+            // B(cb,cs) of the spec for one channel of a separable mode
+            fn blend_component(mode: BlendMode,cb: $t,cs: $t) -> $t {
+                match mode {
+                    BlendMode::Multiply => cb * cs,
+                    BlendMode::Screen => cb + cs - cb * cs,
+                    BlendMode::Overlay => RGB::<$t>::blend_component(BlendMode::HardLight,cs,cb),
+                    BlendMode::Darken => cb.min(cs),
+                    BlendMode::Lighten => cb.max(cs),
+                    BlendMode::ColorDodge => if cb == 0.0 {
+                        0.0
+                    }
+                    else if cs >= 1.0 {
+                        1.0
+                    }
+                    else {
+                        (cb / (1.0 - cs)).min(1.0)
+                    },
+                    BlendMode::ColorBurn => if cb >= 1.0 {
+                        1.0
+                    }
+                    else if cs == 0.0 {
+                        0.0
+                    }
+                    else {
+                        1.0 - ((1.0 - cb) / cs).min(1.0)
+                    },
+                    BlendMode::HardLight => if cs <= 0.5 {
+                        cb * 2.0 * cs
+                    }
+                    else {
+                        let s = 2.0 * cs - 1.0;
+                        cb + s - cb * s
+                    },
+                    BlendMode::SoftLight => if cs <= 0.5 {
+                        cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+                    }
+                    else {
+                        let d = if cb <= 0.25 {
+                            ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                        }
+                        else {
+                            cb.sqrt()
+                        };
+                        cb + (2.0 * cs - 1.0) * (d - cb)
+                    },
+                    BlendMode::Difference => (cb - cs).abs(),
+                    BlendMode::Exclusion => cb + cs - 2.0 * cb * cs,
+                    _ => cs,
+                }
+            }
+
+            // B(Cb,Cs) of the spec, self is the source
+            pub fn blend_mode(&self,backdrop: &RGB<$t>,mode: BlendMode) -> RGB<$t> {
+                let cs = self;
+                let cb = backdrop;
+                match mode {
+                    BlendMode::Hue => cs.set_sat(cb.sat()).set_lum(cb.lum()),
+                    BlendMode::Saturation => cb.set_sat(cs.sat()).set_lum(cb.lum()),
+                    BlendMode::Color => cs.set_lum(cb.lum()),
+                    BlendMode::Luminosity => cb.set_lum(cs.lum()),
+                    _ => RGB {
+                        r: RGB::<$t>::blend_component(mode,cb.r,cs.r),
+                        g: RGB::<$t>::blend_component(mode,cb.g,cs.g),
+                        b: RGB::<$t>::blend_component(mode,cb.b,cs.b),
+                    },
+                }
+            }
+        }
+
+        impl RGBA<$t> {
+            pub fn premultiply(&self) -> RGBA<$t> {
+                RGBA {
+                    r: self.r * self.a,
+                    g: self.g * self.a,
+                    b: self.b * self.a,
+                    a: self.a,
+                }
+            }
+
+            // fully transparent colors become zero
+            pub fn unpremultiply(&self) -> RGBA<$t> {
+                if self.a == 0.0 {
+                    return RGBA::<$t>::zero();
+                }
+                RGBA {
+                    r: self.r / self.a,
+                    g: self.g / self.a,
+                    b: self.b / self.a,
+                    a: self.a,
+                }
+            }
+
+            // Porter-Duff composition of premultiplied colors, self is the source
+            pub fn composite(&self,dst: &RGBA<$t>,op: CompositeOp) -> RGBA<$t> {
+                let (fa,fb) = RGBA::<$t>::porter_duff(op,self.a,dst.a);
+                RGBA {
+                    r: self.r * fa + dst.r * fb,
+                    g: self.g * fa + dst.g * fb,
+                    b: self.b * fa + dst.b * fb,
+                    a: self.a * fa + dst.a * fb,
+                }
+            }
+
+            // blend straight alpha self onto straight alpha backdrop with source-over
+            pub fn blend(&self,backdrop: &RGBA<$t>,mode: BlendMode) -> RGBA<$t> {
+                self.blend_composite(backdrop,mode,CompositeOp::SourceOver)
+            }
+
+            // blend straight alpha self with backdrop, then composite the result
+            // with op, returns straight alpha
+            pub fn blend_composite(&self,backdrop: &RGBA<$t>,mode: BlendMode,op: CompositeOp) -> RGBA<$t> {
+                let cs = RGB::<$t>::new(self.r,self.g,self.b);
+                let cb = RGB::<$t>::new(backdrop.r,backdrop.g,backdrop.b);
+                let b = cs.blend_mode(&cb,mode);
+                let ab = backdrop.a;
+                let mixed = RGBA {
+                    r: (1.0 - ab) * cs.r + ab * b.r,
+                    g: (1.0 - ab) * cs.g + ab * b.g,
+                    b: (1.0 - ab) * cs.b + ab * b.b,
+                    a: self.a,
+                };
+                mixed.premultiply().composite(&backdrop.premultiply(),op).unpremultiply()
+            }
+
+            // (Fa,Fb) for an operator
+            fn porter_duff(op: CompositeOp,sa: $t,da: $t) -> ($t,$t) {
+                match op {
+                    CompositeOp::Clear => (0.0,0.0),
+                    CompositeOp::Copy => (1.0,0.0),
+                    CompositeOp::Destination => (0.0,1.0),
+                    CompositeOp::SourceOver => (1.0,1.0 - sa),
+                    CompositeOp::DestinationOver => (1.0 - da,1.0),
+                    CompositeOp::SourceIn => (da,0.0),
+                    CompositeOp::DestinationIn => (0.0,sa),
+                    CompositeOp::SourceOut => (1.0 - da,0.0),
+                    CompositeOp::DestinationOut => (0.0,1.0 - sa),
+                    CompositeOp::SourceAtop => (da,1.0 - sa),
+                    CompositeOp::DestinationAtop => (1.0 - da,sa),
+                    CompositeOp::Xor => (1.0 - da,1.0 - sa),
+                }
+            }
+        }
+    );
+);
+
+impl_composite!(f32);
+impl_composite!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // co for one channel from (source,destination,source alpha,destination alpha)
+    type Formula = fn(f64,f64,f64,f64) -> f64;
+
+    fn close(a: f64rgba,b: f64rgba) -> bool {
+        ((a.r - b.r).abs() < 1e-12) && ((a.g - b.g).abs() < 1e-12) && ((a.b - b.b).abs() < 1e-12) && ((a.a - b.a).abs() < 1e-12)
+    }
+
+    #[test]
+    fn porter_duff() {
+        // premultiplied source and destination
+        let s = f64rgba::new(0.4,0.2,0.1,0.5);
+        let d = f64rgba::new(0.1,0.3,0.6,0.75);
+        let (sa,da) = (s.a,d.a);
+        // co = cs * Fa + cb * Fb with the factors spelled out per operator
+        let ops: [(CompositeOp,Formula); 12] = [
+            (CompositeOp::Clear,|_,_,_,_| 0.0),
+            (CompositeOp::Copy,|s,_,_,_| s),
+            (CompositeOp::Destination,|_,d,_,_| d),
+            (CompositeOp::SourceOver,|s,d,sa,_| s + d * (1.0 - sa)),
+            (CompositeOp::DestinationOver,|s,d,_,da| s * (1.0 - da) + d),
+            (CompositeOp::SourceIn,|s,_,_,da| s * da),
+            (CompositeOp::DestinationIn,|_,d,sa,_| d * sa),
+            (CompositeOp::SourceOut,|s,_,_,da| s * (1.0 - da)),
+            (CompositeOp::DestinationOut,|_,d,sa,_| d * (1.0 - sa)),
+            (CompositeOp::SourceAtop,|s,d,sa,da| s * da + d * (1.0 - sa)),
+            (CompositeOp::DestinationAtop,|s,d,sa,da| s * (1.0 - da) + d * sa),
+            (CompositeOp::Xor,|s,d,sa,da| s * (1.0 - da) + d * (1.0 - sa)),
+        ];
+        for (op,f) in ops.iter() {
+            let expected = f64rgba::new(f(s.r,d.r,sa,da),f(s.g,d.g,sa,da),f(s.b,d.b,sa,da),f(sa,da,sa,da));
+            assert!(close(s.composite(&d,*op),expected),"{:?}",op);
+        }
+    }
+
+    #[test]
+    fn separable_modes() {
+        // (mode,cb,cs,B(cb,cs))
+        let cases = [
+            (BlendMode::Normal,0.2,0.8,0.8),
+            (BlendMode::Multiply,0.2,0.8,0.16),
+            (BlendMode::Screen,0.2,0.8,0.84),
+            (BlendMode::Overlay,0.2,0.8,0.32),
+            (BlendMode::Overlay,0.8,0.2,0.68),
+            (BlendMode::Darken,0.2,0.8,0.2),
+            (BlendMode::Lighten,0.2,0.8,0.8),
+            (BlendMode::ColorDodge,0.0,1.0,0.0),
+            (BlendMode::ColorDodge,0.1,0.5,0.2),
+            (BlendMode::ColorDodge,0.2,0.8,1.0),
+            (BlendMode::ColorDodge,0.3,1.0,1.0),
+            (BlendMode::ColorBurn,1.0,0.0,1.0),
+            (BlendMode::ColorBurn,0.6,0.8,0.5),
+            (BlendMode::ColorBurn,0.2,0.5,0.0),
+            (BlendMode::ColorBurn,0.5,0.0,0.0),
+            (BlendMode::HardLight,0.2,0.8,0.68),
+            (BlendMode::HardLight,0.6,0.3,0.36),
+            (BlendMode::SoftLight,0.5,0.25,0.375),
+            (BlendMode::SoftLight,0.2,0.8,0.3488),
+            (BlendMode::SoftLight,0.64,0.8,0.736),
+            (BlendMode::Difference,0.2,0.8,0.6),
+            (BlendMode::Exclusion,0.2,0.8,0.68),
+        ];
+        for &(mode,cb,cs,expected) in cases.iter() {
+            let b = f64rgb::new(cs,cs,cs).blend_mode(&f64rgb::new(cb,cb,cb),mode);
+            assert!((b.r - expected).abs() < 1e-12,"{:?} {} {}: {}",mode,cb,cs,b.r);
+            assert!((b.g - expected).abs() < 1e-12);
+            assert!((b.b - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn non_separable_modes() {
+        let cs = f64rgb::new(0.8,0.3,0.1);
+        let cb = f64rgb::new(0.2,0.5,0.6);
+        // (mode,source,backdrop,B(cb,cs)) from SetLum, SetSat and ClipColor in the spec
+        let cases = [
+            (BlendMode::Hue,cs,cb,[0.6335714285714286,0.34785714285714286,0.2335714285714286]),
+            (BlendMode::Saturation,cs,cb,[0.03425,0.55925,0.73425]),
+            (BlendMode::Color,cs,cb,[0.793,0.293,0.093]),
+            (BlendMode::Luminosity,cs,cb,[0.207,0.507,0.607]),
+            (BlendMode::Color,f64rgb::new(1.0,0.0,0.0),f64rgb::new(0.9,0.9,0.9),[1.0,0.8571428571428572,0.8571428571428572]),
+        ];
+        for (mode,s,b,expected) in cases.iter() {
+            let c = s.blend_mode(b,*mode);
+            assert!((c.r - expected[0]).abs() < 1e-12,"{:?}: {}",mode,c);
+            assert!((c.g - expected[1]).abs() < 1e-12,"{:?}: {}",mode,c);
+            assert!((c.b - expected[2]).abs() < 1e-12,"{:?}: {}",mode,c);
+        }
+        // NaN used to panic in SetSat
+        let _ = f64rgb::new(f64::NAN,0.5,0.2).blend_mode(&cb,BlendMode::Hue);
+        let _ = cs.blend_mode(&f64rgb::new(0.1,f64::NAN,0.3),BlendMode::Saturation);
+    }
+
+    #[test]
+    fn blend_with_alpha() {
+        // Cs' = (1 - ab) * Cs + ab * B(Cb,Cs), then source-over
+        let s = f64rgba::new(0.8,0.8,0.8,0.5);
+        let b = f64rgba::new(0.2,0.2,0.2,1.0);
+        let c = s.blend(&b,BlendMode::Multiply);
+        let expected = 0.5 * 0.16 + 0.5 * 0.2;
+        assert!(close(c,f64rgba::new(expected,expected,expected,1.0)),"{}",c);
+        let b = f64rgba::new(0.2,0.2,0.2,0.0);
+        let c = s.blend(&b,BlendMode::Multiply);
+        assert!(close(c,s),"{}",c);
+    }
+}
@@ -0,0 +1,331 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Vector conversions
+
+use std::{array,convert::TryFrom,ops,slice};
+use crate::*;
+
+// Numeric conversion between element types. cast works like `as`,
+// checked_cast returns None if the value does not fit (float to integer
+// truncates first, NaN never fits an integer), saturating_cast clamps to the
+// range of the target type (NaN becomes 0 for integers).
+pub trait Cast<U> {
+    fn cast(self) -> U;
+    fn checked_cast(self) -> Option<U>;
+    fn saturating_cast(self) -> U;
+}
+
+macro_rules! impl_cast_integer (
+    ($s:ty; $($i:ty),*; $($f:ty),*) => (
+        $(
+            impl Cast<$i> for $s {
+                fn cast(self) -> $i {
+                    self as $i
+                }
+
+                fn checked_cast(self) -> Option<$i> {
+                    <$i>::try_from(self).ok()
+                }
+
+                fn saturating_cast(self) -> $i {
+                    match <$i>::try_from(self) {
+                        Ok(v) => v,
+                        Err(_) => if self > 0 {
+                            <$i>::MAX
+                        }
+                        else {
+                            <$i>::MIN
+                        },
+                    }
+                }
+            }
+        )*
+        $(
+            impl Cast<$f> for $s {
+                fn cast(self) -> $f {
+                    self as $f
+                }
+
+                fn checked_cast(self) -> Option<$f> {
+                    Some(self as $f)
+                }
+
+                fn saturating_cast(self) -> $f {
+                    self as $f
+                }
+            }
+        )*
+    );
+);
+
+macro_rules! impl_cast_float (
+    ($s:ty; $($i:ty),*; $($f:ty),*) => (
+        $(
+            impl Cast<$i> for $s {
+                fn cast(self) -> $i {
+                    self as $i
+                }
+
+                // MAX + 1 is a power of two, so it is exact even when MAX is not
+                fn checked_cast(self) -> Option<$i> {
+                    let t = self.trunc();
+                    if (t >= <$i>::MIN as $s) && (t < <$i>::MAX as $s + 1.0) {
+                        Some(t as $i)
+                    }
+                    else {
+                        None
+                    }
+                }
+
+                fn saturating_cast(self) -> $i {
+                    self as $i
+                }
+            }
+        )*
+        $(
+            impl Cast<$f> for $s {
+                fn cast(self) -> $f {
+                    self as $f
+                }
+
+                fn checked_cast(self) -> Option<$f> {
+                    let v = self as $f;
+                    if v.is_infinite() && self.is_finite() {
+                        None
+                    }
+                    else {
+                        Some(v)
+                    }
+                }
+
+                fn saturating_cast(self) -> $f {
+                    let v = self as $f;
+                    if v.is_infinite() && self.is_finite() {
+                        if self > 0.0 {
+                            <$f>::MAX
+                        }
+                        else {
+                            <$f>::MIN
+                        }
+                    }
+                    else {
+                        v
+                    }
+                }
+            }
+        )*
+    );
+);
+
+impl_cast_integer!(u8; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(i8; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(u16; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(i16; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(u32; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(i32; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(u64; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(i64; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(usize; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_integer!(isize; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_float!(f32; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+impl_cast_float!(f64; u8,i8,u16,i16,u32,i32,u64,i64,usize,isize; f32,f64);
+
+// Vectors are repr(C), so their components can be viewed as an array.
+macro_rules! impl_vector_convert (
+    ($v:ident; $n:expr; $($c:ident: $i:expr),*; $($tt:ty),*) => (
+        impl<T> From<[T; $n]> for $v<T> {
+            fn from(a: [T; $n]) -> $v<T> {
+                let [$($c),*] = a;
+                $v {
+                    $($c: $c,)*
+                }
+            }
+        }
+
+        impl<T> From<$v<T>> for [T; $n] {
+            fn from(v: $v<T>) -> [T; $n] {
+                [$(v.$c),*]
+            }
+        }
+
+        impl<T> From<($($tt),*)> for $v<T> {
+            fn from(t: ($($tt),*)) -> $v<T> {
+                let ($($c),*) = t;
+                $v {
+                    $($c: $c,)*
+                }
+            }
+        }
+
+        impl<T> From<$v<T>> for ($($tt),*) {
+            fn from(v: $v<T>) -> ($($tt),*) {
+                ($(v.$c),*)
+            }
+        }
+
+        impl<T> AsRef<[T; $n]> for $v<T> {
+            fn as_ref(&self) -> &[T; $n] {
+                unsafe { &*(self as *const $v<T> as *const [T; $n]) }
+            }
+        }
+
+        impl<T> AsMut<[T; $n]> for $v<T> {
+            fn as_mut(&mut self) -> &mut [T; $n] {
+                unsafe { &mut *(self as *mut $v<T> as *mut [T; $n]) }
+            }
+        }
+
+        // panics if the index is out of range, like arrays do
+        impl<T> ops::Index<usize> for $v<T> {
+            type Output = T;
+            fn index(&self,index: usize) -> &T {
+                match index {
+                    $($i => &self.$c,)*
+                    _ => panic!("index out of range: the len is {} but the index is {}",$n,index),
+                }
+            }
+        }
+
+        impl<T> ops::IndexMut<usize> for $v<T> {
+            fn index_mut(&mut self,index: usize) -> &mut T {
+                match index {
+                    $($i => &mut self.$c,)*
+                    _ => panic!("index out of range: the len is {} but the index is {}",$n,index),
+                }
+            }
+        }
+
+        impl<T> $v<T> {
+            pub fn iter(&self) -> slice::Iter<'_,T> {
+                self.as_ref().iter()
+            }
+
+            pub fn iter_mut(&mut self) -> slice::IterMut<'_,T> {
+                self.as_mut().iter_mut()
+            }
+
+            pub fn cast<U>(self) -> $v<U> where T: Cast<U> {
+                $v {
+                    $($c: self.$c.cast(),)*
+                }
+            }
+
+            // None if any component does not fit
+            pub fn checked_cast<U>(self) -> Option<$v<U>> where T: Cast<U> {
+                Some($v {
+                    $($c: self.$c.checked_cast()?,)*
+                })
+            }
+
+            pub fn saturating_cast<U>(self) -> $v<U> where T: Cast<U> {
+                $v {
+                    $($c: self.$c.saturating_cast(),)*
+                }
+            }
+        }
+
+        impl<T> IntoIterator for $v<T> {
+            type Item = T;
+            type IntoIter = array::IntoIter<T,$n>;
+            fn into_iter(self) -> Self::IntoIter {
+                IntoIterator::into_iter(<[T; $n]>::from(self))
+            }
+        }
+
+        impl<'a,T> IntoIterator for &'a $v<T> {
+            type Item = &'a T;
+            type IntoIter = slice::Iter<'a,T>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+
+        impl<'a,T> IntoIterator for &'a mut $v<T> {
+            type Item = &'a mut T;
+            type IntoIter = slice::IterMut<'a,T>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter_mut()
+            }
+        }
+    );
+);
+
+impl_vector_convert!(XY; 2; x: 0,y: 1; T,T);
+impl_vector_convert!(XYZ; 3; x: 0,y: 1,z: 2; T,T,T);
+impl_vector_convert!(XYZW; 4; x: 0,y: 1,z: 2,w: 3; T,T,T,T);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrays_and_tuples() {
+        let v = i32xy::from([1,2]);
+        assert_eq!(v,i32xy::new(1,2));
+        assert_eq!(<[i32; 2]>::from(v),[1,2]);
+        assert_eq!(<(i32,i32)>::from(i32xy::from((3,4))),(3,4));
+        let v = i32xyz::from([1,2,3]);
+        assert_eq!(v,i32xyz::new(1,2,3));
+        assert_eq!(<[i32; 3]>::from(v),[1,2,3]);
+        assert_eq!(<(i32,i32,i32)>::from(i32xyz::from((4,5,6))),(4,5,6));
+        let v = i32xyzw::from([1,2,3,4]);
+        assert_eq!(v,i32xyzw::new(1,2,3,4));
+        assert_eq!(<[i32; 4]>::from(v),[1,2,3,4]);
+        assert_eq!(<(i32,i32,i32,i32)>::from(i32xyzw::from((5,6,7,8))),(5,6,7,8));
+    }
+
+    #[test]
+    fn as_ref_matches_fields() {
+        let mut v = f32xy::new(1.0,2.0);
+        assert_eq!(v.as_ref(),&[v.x,v.y]);
+        v.as_mut()[1] = 5.0;
+        assert_eq!(v.y,5.0);
+        let mut v = f32xyz::new(1.0,2.0,3.0);
+        assert_eq!(v.as_ref(),&[v.x,v.y,v.z]);
+        v.as_mut()[2] = 5.0;
+        assert_eq!(v.z,5.0);
+        let mut v = f32xyzw::new(1.0,2.0,3.0,4.0);
+        assert_eq!(v.as_ref(),&[v.x,v.y,v.z,v.w]);
+        v.as_mut()[3] = 5.0;
+        assert_eq!(v.w,5.0);
+        for (i,c) in v.iter().enumerate() {
+            assert_eq!(*c,v[i]);
+        }
+        v[0] = 6.0;
+        assert_eq!(v.x,6.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of range")]
+    fn index_out_of_range() {
+        let v = i32xyz::new(1,2,3);
+        let _ = v[3];
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of range")]
+    fn index_mut_out_of_range() {
+        let mut v = i32xy::new(1,2);
+        v[2] = 0;
+    }
+
+    #[test]
+    fn checked_cast() {
+        assert_eq!(i32xy::new(1,255).checked_cast::<u8>(),Some(XY::<u8>::new(1,255)));
+        assert_eq!(i32xy::new(1,256).checked_cast::<u8>(),None);
+        assert_eq!(i32xy::new(-1,0).checked_cast::<u8>(),None);
+        assert_eq!(f32xyz::new(-0.5,1.9,255.9).checked_cast::<u8>(),Some(XYZ::<u8>::new(0,1,255)));
+        assert_eq!(f32xyz::new(0.0,256.0,0.0).checked_cast::<u8>(),None);
+        assert_eq!(f32xyz::new(0.0,f32::NAN,0.0).checked_cast::<u8>(),None);
+        assert_eq!(f64xy::new(1e300,0.0).checked_cast::<f32>(),None);
+        assert_eq!(Cast::<i64>::checked_cast(9.3e18f64),None);
+    }
+
+    #[test]
+    fn saturating_cast() {
+        assert_eq!(f32xyzw::new(-1.0,300.0,f32::NAN,12.7).saturating_cast::<u8>(),XYZW::<u8>::new(0,255,0,12));
+        assert_eq!(i32xyz::new(-1,300,12).saturating_cast::<u8>(),XYZ::<u8>::new(0,255,12));
+        assert_eq!(i64xy::new(i64::MIN,i64::MAX).saturating_cast::<i32>(),i32xy::new(i32::MIN,i32::MAX));
+        assert_eq!(f64xy::new(-1e300,1e300).saturating_cast::<f32>(),f32xy::new(f32::MIN,f32::MAX));
+    }
+}
@@ -5,8 +5,10 @@
 use std::{cmp,fmt,ops};
 use crate::*;
 
-pub const TAU: f32 = 6.28318531;
+pub const TAU: f32 = std::f32::consts::TAU;
 
+// The rotation is Mat3x3::yaw(y) * Mat3x3::pitch(p) * Mat3x3::roll(b): bank
+// around Z first, then pitch around X, then yaw around Y.
 #[derive(Copy,Clone)]
 pub struct YPB<T> {
     pub y: T,
@@ -24,6 +26,59 @@ macro_rules! impl_ypb (
                     b: b,
                 }
             }
+
+            pub fn to_matrix3(&self) -> Mat3x3<$t> {
+                Mat3x3::<$t>::yaw(self.y) * Mat3x3::<$t>::pitch(self.p) * Mat3x3::<$t>::roll(self.b)
+            }
+
+            pub fn to_matrix4(&self) -> Mat4x4<$t> {
+                Mat4x4::<$t>::yaw(self.y) * Mat4x4::<$t>::pitch(self.p) * Mat4x4::<$t>::roll(self.b)
+            }
+
+            pub fn to_quaternion(&self) -> Quaternion<$t> {
+                Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(0.0,1.0,0.0),self.y)
+                    * Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(1.0,0.0,0.0),self.p)
+                    * Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(0.0,0.0,1.0),self.b)
+            }
+        }
+
+        impl From<Mat3x3<$t>> for YPB<$t> {
+            fn from(m: Mat3x3<$t>) -> YPB<$t> {
+                let cp = (m.x.y * m.x.y + m.y.y * m.y.y).sqrt();
+                let p = (-m.z.y).atan2(cp);
+                if cp > <$t>::EPSILON.sqrt() {
+                    YPB {
+                        y: m.z.x.atan2(m.z.z),
+                        p: p,
+                        b: m.x.y.atan2(m.y.y),
+                    }
+                }
+                else {
+                    // gimbal lock: pitch is +-90 degrees and only yaw +- bank is
+                    // defined, so put everything in yaw
+                    YPB {
+                        y: (-m.x.z).atan2(m.x.x),
+                        p: p,
+                        b: 0.0,
+                    }
+                }
+            }
+        }
+
+        impl From<Mat4x4<$t>> for YPB<$t> {
+            fn from(m: Mat4x4<$t>) -> YPB<$t> {
+                YPB::<$t>::from(Mat3x3 {
+                    x: XYZ::<$t>::new(m.x.x,m.x.y,m.x.z),
+                    y: XYZ::<$t>::new(m.y.x,m.y.y,m.y.z),
+                    z: XYZ::<$t>::new(m.z.x,m.z.y,m.z.z),
+                })
+            }
+        }
+
+        impl From<Quaternion<$t>> for YPB<$t> {
+            fn from(q: Quaternion<$t>) -> YPB<$t> {
+                YPB::<$t>::from(Mat3x3::<$t>::from(q))
+            }
         }
 
         impl cmp::PartialEq for YPB<$t> {
@@ -165,3 +220,400 @@ impl_ypb!(f32);
 pub type f64ypb = YPB<f64>;
 
 impl_ypb!(f64);
+
+// Rotation sequences: Tait-Bryan (three different axes) and proper Euler
+// (first and last axis the same). Intrinsic rotations turn around the
+// rotated axes, extrinsic rotations around the fixed axes.
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum EulerOrder {
+    IntrinsicXYZ,
+    IntrinsicXZY,
+    IntrinsicYXZ,
+    IntrinsicYZX,
+    IntrinsicZXY,
+    IntrinsicZYX,
+    IntrinsicXYX,
+    IntrinsicXZX,
+    IntrinsicYXY,
+    IntrinsicYZY,
+    IntrinsicZXZ,
+    IntrinsicZYZ,
+    ExtrinsicXYZ,
+    ExtrinsicXZY,
+    ExtrinsicYXZ,
+    ExtrinsicYZX,
+    ExtrinsicZXY,
+    ExtrinsicZYX,
+    ExtrinsicXYX,
+    ExtrinsicXZX,
+    ExtrinsicYXY,
+    ExtrinsicYZY,
+    ExtrinsicZXZ,
+    ExtrinsicZYZ,
+}
+
+impl EulerOrder {
+    // axes in order of application (0 = X, 1 = Y, 2 = Z), and whether they are intrinsic
+    fn axes(self) -> ([usize; 3],bool) {
+        match self {
+            EulerOrder::IntrinsicXYZ => ([0,1,2],true),
+            EulerOrder::IntrinsicXZY => ([0,2,1],true),
+            EulerOrder::IntrinsicYXZ => ([1,0,2],true),
+            EulerOrder::IntrinsicYZX => ([1,2,0],true),
+            EulerOrder::IntrinsicZXY => ([2,0,1],true),
+            EulerOrder::IntrinsicZYX => ([2,1,0],true),
+            EulerOrder::IntrinsicXYX => ([0,1,0],true),
+            EulerOrder::IntrinsicXZX => ([0,2,0],true),
+            EulerOrder::IntrinsicYXY => ([1,0,1],true),
+            EulerOrder::IntrinsicYZY => ([1,2,1],true),
+            EulerOrder::IntrinsicZXZ => ([2,0,2],true),
+            EulerOrder::IntrinsicZYZ => ([2,1,2],true),
+            EulerOrder::ExtrinsicXYZ => ([0,1,2],false),
+            EulerOrder::ExtrinsicXZY => ([0,2,1],false),
+            EulerOrder::ExtrinsicYXZ => ([1,0,2],false),
+            EulerOrder::ExtrinsicYZX => ([1,2,0],false),
+            EulerOrder::ExtrinsicZXY => ([2,0,1],false),
+            EulerOrder::ExtrinsicZYX => ([2,1,0],false),
+            EulerOrder::ExtrinsicXYX => ([0,1,0],false),
+            EulerOrder::ExtrinsicXZX => ([0,2,0],false),
+            EulerOrder::ExtrinsicYXY => ([1,0,1],false),
+            EulerOrder::ExtrinsicYZY => ([1,2,1],false),
+            EulerOrder::ExtrinsicZXZ => ([2,0,2],false),
+            EulerOrder::ExtrinsicZYZ => ([2,1,2],false),
+        }
+    }
+}
+
+// Angles a, b and c are applied in the order given by order. YPB is the same
+// as EulerOrder::IntrinsicYXZ with (y,p,b).
+#[derive(Copy,Clone)]
+pub struct Euler<T> {
+    pub a: T,
+    pub b: T,
+    pub c: T,
+    pub order: EulerOrder,
+}
+
+macro_rules! impl_euler (
+    ($t:ty) => (
+        impl Euler<$t> {
+            pub fn new(a: $t,b: $t,c: $t,order: EulerOrder) -> Euler<$t> {
+                Euler {
+                    a: a,
+                    b: b,
+                    c: c,
+                    order: order,
+                }
+            }
+
+            fn axis_matrix(axis: usize,a: $t) -> Mat3x3<$t> {
+                match axis {
+                    0 => Mat3x3::<$t>::pitch(a),
+                    1 => Mat3x3::<$t>::yaw(a),
+                    _ => Mat3x3::<$t>::roll(a),
+                }
+            }
+
+            fn axis_quaternion(axis: usize,a: $t) -> Quaternion<$t> {
+                match axis {
+                    0 => Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(1.0,0.0,0.0),a),
+                    1 => Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(0.0,1.0,0.0),a),
+                    _ => Quaternion::<$t>::from_axis_angle(XYZ::<$t>::new(0.0,0.0,1.0),a),
+                }
+            }
+
+            pub fn to_matrix3(&self) -> Mat3x3<$t> {
+                let (axes,intrinsic) = self.order.axes();
+                let ma = Euler::<$t>::axis_matrix(axes[0],self.a);
+                let mb = Euler::<$t>::axis_matrix(axes[1],self.b);
+                let mc = Euler::<$t>::axis_matrix(axes[2],self.c);
+                if intrinsic {
+                    ma * mb * mc
+                }
+                else {
+                    mc * mb * ma
+                }
+            }
+
+            pub fn to_matrix4(&self) -> Mat4x4<$t> {
+                let m = self.to_matrix3();
+                Mat4x4 {
+                    x: XYZW::<$t>::new(m.x.x,m.x.y,m.x.z,0.0),
+                    y: XYZW::<$t>::new(m.y.x,m.y.y,m.y.z,0.0),
+                    z: XYZW::<$t>::new(m.z.x,m.z.y,m.z.z,0.0),
+                    w: XYZW::<$t>::new(0.0,0.0,0.0,1.0),
+                }
+            }
+
+            pub fn to_quaternion(&self) -> Quaternion<$t> {
+                let (axes,intrinsic) = self.order.axes();
+                let qa = Euler::<$t>::axis_quaternion(axes[0],self.a);
+                let qb = Euler::<$t>::axis_quaternion(axes[1],self.b);
+                let qc = Euler::<$t>::axis_quaternion(axes[2],self.c);
+                if intrinsic {
+                    qa * qb * qc
+                }
+                else {
+                    qc * qb * qa
+                }
+            }
+
+            pub fn from_matrix3(m: Mat3x3<$t>,order: EulerOrder) -> Euler<$t> {
+                let (axes,intrinsic) = order.axes();
+
+                // extrinsic (i,j,k) with (a,b,c) is intrinsic (k,j,i) with (c,b,a)
+                let (i,j) = if intrinsic {
+                    (axes[0],axes[1])
+                }
+                else {
+                    (axes[2],axes[1])
+                };
+                let k = 3 - i - j;
+                let s: $t = if (j + 3 - i) % 3 == 1 {
+                    1.0
+                }
+                else {
+                    -1.0
+                };
+
+                // m[row][column]
+                let m = [
+                    [m.x.x,m.y.x,m.z.x],
+                    [m.x.y,m.y.y,m.z.y],
+                    [m.x.z,m.y.z,m.z.z],
+                ];
+                let lock = <$t>::EPSILON.sqrt();
+                let (first,middle,last) = if axes[0] == axes[2] {
+                    let sb = (m[i][j] * m[i][j] + m[i][k] * m[i][k]).sqrt();
+                    let b = sb.atan2(m[i][i]);
+                    if sb > lock {
+                        (m[j][i].atan2(-s * m[k][i]),b,m[i][j].atan2(s * m[i][k]))
+                    }
+                    else {
+                        // gimbal lock: only first +- last is defined, so put everything in first
+                        ((s * m[k][j]).atan2(m[j][j]),b,0.0)
+                    }
+                }
+                else {
+                    let cb = (m[i][i] * m[i][i] + m[i][j] * m[i][j]).sqrt();
+                    let b = (s * m[i][k]).atan2(cb);
+                    if cb > lock {
+                        ((-s * m[j][k]).atan2(m[k][k]),b,(-s * m[i][j]).atan2(m[i][i]))
+                    }
+                    else {
+                        ((s * m[k][j]).atan2(m[j][j]),b,0.0)
+                    }
+                };
+                if intrinsic {
+                    Euler::<$t>::new(first,middle,last,order)
+                }
+                else {
+                    Euler::<$t>::new(last,middle,first,order)
+                }
+            }
+
+            pub fn from_quaternion(q: Quaternion<$t>,order: EulerOrder) -> Euler<$t> {
+                Euler::<$t>::from_matrix3(Mat3x3::<$t>::from(q),order)
+            }
+        }
+
+        impl cmp::PartialEq for Euler<$t> {
+            fn eq(&self,other: &Euler<$t>) -> bool {
+                (self.a == other.a)
+                && (self.b == other.b)
+                && (self.c == other.c)
+                && (self.order == other.order)
+            }
+        }
+
+        impl fmt::Display for Euler<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{}; {:?})",self.a,self.b,self.c,self.order)
+            }
+        }
+
+        impl fmt::Debug for Euler<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{}; {:?})",self.a,self.b,self.c,self.order)
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type f32euler = Euler<f32>;
+
+impl_euler!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64euler = Euler<f64>;
+
+impl_euler!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2,PI};
+
+    const ORDERS: [EulerOrder; 24] = [
+        EulerOrder::IntrinsicXYZ,EulerOrder::IntrinsicXZY,EulerOrder::IntrinsicYXZ,
+        EulerOrder::IntrinsicYZX,EulerOrder::IntrinsicZXY,EulerOrder::IntrinsicZYX,
+        EulerOrder::IntrinsicXYX,EulerOrder::IntrinsicXZX,EulerOrder::IntrinsicYXY,
+        EulerOrder::IntrinsicYZY,EulerOrder::IntrinsicZXZ,EulerOrder::IntrinsicZYZ,
+        EulerOrder::ExtrinsicXYZ,EulerOrder::ExtrinsicXZY,EulerOrder::ExtrinsicYXZ,
+        EulerOrder::ExtrinsicYZX,EulerOrder::ExtrinsicZXY,EulerOrder::ExtrinsicZYX,
+        EulerOrder::ExtrinsicXYX,EulerOrder::ExtrinsicXZX,EulerOrder::ExtrinsicYXY,
+        EulerOrder::ExtrinsicYZY,EulerOrder::ExtrinsicZXZ,EulerOrder::ExtrinsicZYZ,
+    ];
+
+    fn proper(order: EulerOrder) -> bool {
+        let (axes,_) = order.axes();
+        axes[0] == axes[2]
+    }
+
+    fn same_matrix(m: Mat3x3<f64>,n: Mat3x3<f64>) -> bool {
+        let close = |a: XYZ<f64>,b: XYZ<f64>| XYZ::<f64>::dot(a - b,a - b) < 1e-20;
+        close(m.x,n.x) && close(m.y,n.y) && close(m.z,n.z)
+    }
+
+    fn same_rotation(p: f64q,q: f64q) -> bool {
+        1.0 - f64q::dot(p,q).abs() < 1e-12
+    }
+
+    // angles inside the canonical ranges, away from gimbal lock
+    fn regular(order: EulerOrder) -> Vec<(f64,f64,f64)> {
+        if proper(order) {
+            vec![(0.3,0.7,1.1),(-2.5,2.2,2.9),(1.9,0.1,-0.4)]
+        }
+        else {
+            vec![(0.3,-0.7,1.1),(-2.5,1.2,2.9),(1.9,0.1,-3.0)]
+        }
+    }
+
+    // middle angle at the singularities
+    fn locked(order: EulerOrder) -> Vec<(f64,f64,f64)> {
+        if proper(order) {
+            vec![(0.4,0.0,-0.9),(0.4,PI,-0.9)]
+        }
+        else {
+            vec![(0.4,FRAC_PI_2,-0.9),(0.4,-FRAC_PI_2,-0.9)]
+        }
+    }
+
+    #[test]
+    fn quaternion_matches_matrix() {
+        for order in ORDERS.iter() {
+            for (a,b,c) in regular(*order) {
+                let e = f64euler::new(a,b,c,*order);
+                assert!(same_matrix(Mat3x3::<f64>::from(e.to_quaternion()),e.to_matrix3()),"{:?}",order);
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        for order in ORDERS.iter() {
+            for (a,b,c) in regular(*order) {
+                let e = f64euler::new(a,b,c,*order);
+                let r = f64euler::from_matrix3(e.to_matrix3(),*order);
+                assert!(((r.a - a).abs() < 1e-9) && ((r.b - b).abs() < 1e-9) && ((r.c - c).abs() < 1e-9),"{:?} {:?}",order,r);
+                assert_eq!(r.order,*order);
+            }
+        }
+    }
+
+    #[test]
+    fn quaternion_round_trip() {
+        for order in ORDERS.iter() {
+            for (a,b,c) in regular(*order) {
+                let e = f64euler::new(a,b,c,*order);
+                let r = f64euler::from_quaternion(e.to_quaternion(),*order);
+                assert!(((r.a - a).abs() < 1e-9) && ((r.b - b).abs() < 1e-9) && ((r.c - c).abs() < 1e-9),"{:?} {:?}",order,r);
+            }
+        }
+    }
+
+    // in gimbal lock the angles are not unique, but the rotation must survive
+    #[test]
+    fn gimbal_lock_round_trip() {
+        for order in ORDERS.iter() {
+            for (a,b,c) in locked(*order) {
+                let e = f64euler::new(a,b,c,*order);
+                let r = f64euler::from_matrix3(e.to_matrix3(),*order);
+                assert!(same_matrix(r.to_matrix3(),e.to_matrix3()),"{:?} {:?}",order,r);
+                assert!((r.b - b).abs() < 1e-6,"{:?} {:?}",order,r);
+                let r = f64euler::from_quaternion(e.to_quaternion(),*order);
+                assert!(same_rotation(r.to_quaternion(),e.to_quaternion()),"{:?} {:?}",order,r);
+            }
+        }
+    }
+
+    // every rotation on a grid survives the trip through angles, whatever the angles
+    #[test]
+    fn round_trip_sweep() {
+        let steps: Vec<f64> = (0..9).map(|i| -PI + 2.0 * PI * (i as f64) / 8.0).collect();
+        for order in ORDERS.iter() {
+            for a in steps.iter() {
+                for b in steps.iter() {
+                    for c in steps.iter() {
+                        let e = f64euler::new(*a,*b,*c,*order);
+                        let m = e.to_matrix3();
+                        assert!(same_matrix(f64euler::from_matrix3(m,*order).to_matrix3(),m),"{:?} {:?}",order,e);
+                        let q = e.to_quaternion();
+                        assert!(same_rotation(f64euler::from_quaternion(q,*order).to_quaternion(),q),"{:?} {:?}",order,e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn same_ypb(r: f64ypb,e: f64ypb) -> bool {
+        ((r.y - e.y).abs() < 1e-9) && ((r.p - e.p).abs() < 1e-9) && ((r.b - e.b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn ypb_round_trip() {
+        for &(y,p,b) in [(0.3,-0.7,1.1),(-2.5,1.2,2.9),(1.9,0.1,-3.0),(0.0,0.0,0.0)].iter() {
+            let e = f64ypb::new(y,p,b);
+            let r = f64ypb::from(e.to_matrix3());
+            assert!(same_ypb(r,e),"{} {}",e,r);
+            let r = f64ypb::from(e.to_matrix4());
+            assert!(same_ypb(r,e),"{} {}",e,r);
+            let r = f64ypb::from(e.to_quaternion());
+            assert!(same_ypb(r,e),"{} {}",e,r);
+        }
+    }
+
+    // bank around Z first, then pitch around X, then yaw around Y
+    #[test]
+    fn ypb_order() {
+        let e = f64ypb::new(0.3,-0.7,1.1);
+        let m = e.to_matrix3();
+        let v = XYZ::<f64>::new(1.0,2.0,3.0);
+        let stepped = Mat3x3::<f64>::yaw(e.y) * (Mat3x3::<f64>::pitch(e.p) * (Mat3x3::<f64>::roll(e.b) * v));
+        let d = m * v - stepped;
+        assert!(XYZ::<f64>::dot(d,d) < 1e-20);
+        assert!(same_matrix(m,f64euler::new(e.y,e.p,e.b,EulerOrder::IntrinsicYXZ).to_matrix3()));
+        assert!(same_matrix(m,Mat3x3::<f64>::from(e.to_quaternion())));
+        let m4 = e.to_matrix4();
+        let n = Mat3x3::<f64>::new(m4.x.xyz(),m4.y.xyz(),m4.z.xyz());
+        assert!(same_matrix(m,n));
+    }
+
+    // at pitch +-90 degrees yaw and bank turn around the same axis, so
+    // everything goes into yaw
+    #[test]
+    fn ypb_gimbal_lock() {
+        for &p in [FRAC_PI_2,-FRAC_PI_2].iter() {
+            let e = f64ypb::new(0.4,p,-0.9);
+            let m = e.to_matrix3();
+            let r = f64ypb::from(m);
+            assert!((r.p - p).abs() < 1e-6,"{}",r);
+            assert_eq!(r.b,0.0);
+            assert!(same_matrix(r.to_matrix3(),m),"{}",r);
+            let r = f64ypb::from(e.to_quaternion());
+            assert_eq!(r.b,0.0);
+            assert!(same_rotation(r.to_quaternion(),e.to_quaternion()),"{}",r);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Fast Fourier transforms
+
+use std::f64::consts::PI;
+use crate::*;
+
+// The module is private, the transforms are associated functions of
+// Complex<T>, like f32c::fft(&mut data) or f64c::rfft(&samples).
+// Forward transforms use exp(-2 pi i jk / n) and are unscaled, inverse
+// transforms use exp(2 pi i jk / n) and scale by 1 / n. Power of two lengths
+// use radix-2, lengths with no prime factor above 7 use mixed radix
+// Cooley-Tukey, and all other lengths go through Bluestein's algorithm.
+macro_rules! impl_fft (
+    ($t:ty) => (
+        impl Complex<$t> {
+            pub fn fft(data: &mut [Complex<$t>]) {
+                Complex::<$t>::transform(data,false);
+            }
+
+            pub fn ifft(data: &mut [Complex<$t>]) {
+                Complex::<$t>::transform(data,true);
+                let n = data.len();
+                if n > 1 {
+                    let s = 1.0 / n as $t;
+                    for z in data.iter_mut() {
+                        *z *= s;
+                    }
+                }
+            }
+
+            // spectrum of real input, only the n / 2 + 1 non-redundant bins
+            pub fn rfft(input: &[$t]) -> Vec<Complex<$t>> {
+                let n = input.len();
+                if (n < 2) || (n % 2 != 0) {
+                    let mut data: Vec<Complex<$t>> = input.iter().map(|x| Complex::<$t>::new(*x,0.0)).collect();
+                    Complex::<$t>::fft(&mut data);
+                    data.truncate(n / 2 + 1);
+                    return data;
+                }
+
+                // pack even and odd samples into one half-length complex transform
+                let h = n / 2;
+                let mut z: Vec<Complex<$t>> = (0..h).map(|j| Complex::<$t>::new(input[2 * j],input[2 * j + 1])).collect();
+                Complex::<$t>::fft(&mut z);
+                let mut result = Vec::with_capacity(h + 1);
+                for k in 0..=h {
+                    let a = z[k % h];
+                    let b = z[(h - k) % h].conj();
+                    let even = (a + b) * 0.5;
+                    let odd = (a - b) * 0.5;
+                    let w = Complex::<$t>::twiddle(k,n,false);
+                    // (w * odd) / i
+                    let wo = w * odd;
+                    result.push(even + Complex::<$t>::new(wo.i,-wo.r));
+                }
+                result
+            }
+
+            // inverse of rfft, n is the length of the original real signal
+            pub fn irfft(spectrum: &[Complex<$t>],n: usize) -> Vec<$t> {
+                let bin = |k: usize| if k < spectrum.len() {
+                    spectrum[k]
+                }
+                else {
+                    Complex::zero()
+                };
+                let mut data: Vec<Complex<$t>> = (0..n).map(|k| if k <= n / 2 {
+                    bin(k)
+                }
+                else {
+                    bin(n - k).conj()
+                }).collect();
+                Complex::<$t>::ifft(&mut data);
+                data.iter().map(|z| z.r).collect()
+            }
+
+            // 2D transform of a row-major width x height buffer
+            pub fn fft2(data: &mut [Complex<$t>],width: usize,height: usize) {
+                Complex::<$t>::transform2(data,width,height,false);
+            }
+
+            pub fn ifft2(data: &mut [Complex<$t>],width: usize,height: usize) {
+                Complex::<$t>::transform2(data,width,height,true);
+                let n = width * height;
+                if n > 1 {
+                    let s = 1.0 / n as $t;
+                    for z in data[0..n].iter_mut() {
+                        *z *= s;
+                    }
+                }
+            }
+
+            fn twiddle(k: usize,n: usize,inverse: bool) -> Complex<$t> {
+                let a = 2.0 * PI * (k as f64) / (n as f64);
+                let a = if inverse {
+                    a
+                }
+                else {
+                    -a
+                };
+                Complex::<$t>::new(a.cos() as $t,a.sin() as $t)
+            }
+
+            fn transform(data: &mut [Complex<$t>],inverse: bool) {
+                let n = data.len();
+                if n < 2 {
+                    return;
+                }
+                if n.is_power_of_two() {
+                    Complex::<$t>::radix2(data,inverse);
+                }
+                else if Complex::<$t>::largest_factor(n) <= 7 {
+                    let twiddles: Vec<Complex<$t>> = (0..n).map(|k| Complex::<$t>::twiddle(k,n,inverse)).collect();
+                    Complex::<$t>::mixed_radix(data,&twiddles,1);
+                }
+                else {
+                    Complex::<$t>::bluestein(data,inverse);
+                }
+            }
+
+            fn transform2(data: &mut [Complex<$t>],width: usize,height: usize,inverse: bool) {
+                let n = width.checked_mul(height).expect("fft2: width * height overflows");
+                assert!(data.len() >= n,"fft2: a {}x{} transform needs {} values, data holds {}",width,height,n,data.len());
+                if n == 0 {
+                    return;
+                }
+                for row in data[0..width * height].chunks_mut(width) {
+                    Complex::<$t>::transform(row,inverse);
+                }
+                let mut column = vec![Complex::<$t>::zero(); height];
+                for x in 0..width {
+                    for y in 0..height {
+                        column[y] = data[y * width + x];
+                    }
+                    Complex::<$t>::transform(&mut column,inverse);
+                    for y in 0..height {
+                        data[y * width + x] = column[y];
+                    }
+                }
+            }
+
+            fn radix2(data: &mut [Complex<$t>],inverse: bool) {
+                let n = data.len();
+                let bits = n.trailing_zeros();
+                for i in 0..n {
+                    let j = i.reverse_bits() >> (usize::BITS - bits);
+                    if j > i {
+                        data.swap(i,j);
+                    }
+                }
+                let twiddles: Vec<Complex<$t>> = (0..n / 2).map(|k| Complex::<$t>::twiddle(k,n,inverse)).collect();
+                let mut size = 2;
+                while size <= n {
+                    let half = size / 2;
+                    let step = n / size;
+                    for start in (0..n).step_by(size) {
+                        for k in 0..half {
+                            let a = data[start + k];
+                            let b = data[start + k + half] * twiddles[k * step];
+                            data[start + k] = a + b;
+                            data[start + k + half] = a - b;
+                        }
+                    }
+                    size *= 2;
+                }
+            }
+
+            fn smallest_factor(n: usize) -> usize {
+                (2..).find(|p| n % p == 0).unwrap()
+            }
+
+            fn largest_factor(mut n: usize) -> usize {
+                let mut p = 2;
+                while n > 1 {
+                    p = Complex::<$t>::smallest_factor(n);
+                    while n % p == 0 {
+                        n /= p;
+                    }
+                }
+                p
+            }
+
+            // decimation in time, splitting off the smallest prime factor p of n,
+            // twiddles holds the roots of unity for the full length
+            fn mixed_radix(data: &mut [Complex<$t>],twiddles: &[Complex<$t>],stride: usize) {
+                let n = data.len();
+                if n < 2 {
+                    return;
+                }
+                let p = Complex::<$t>::smallest_factor(n);
+                let m = n / p;
+                let mut parts: Vec<Vec<Complex<$t>>> = (0..p).map(|r| (0..m).map(|j| data[j * p + r]).collect()).collect();
+                for part in parts.iter_mut() {
+                    Complex::<$t>::mixed_radix(part,twiddles,stride * p);
+                }
+                for k in 0..m {
+                    for q in 0..p {
+                        let e = k + q * m;
+                        let mut sum = parts[0][k];
+                        for r in 1..p {
+                            sum += parts[r][k] * twiddles[(r * e) % n * stride];
+                        }
+                        data[e] = sum;
+                    }
+                }
+            }
+
+            // jk = (j^2 + k^2 - (k - j)^2) / 2 turns the transform into a convolution
+            fn bluestein(data: &mut [Complex<$t>],inverse: bool) {
+                let n = data.len();
+                let m = (2 * n - 1).next_power_of_two();
+                let chirp: Vec<Complex<$t>> = (0..n).map(|j| {
+                    let jj = ((j as u128 * j as u128) % (2 * n as u128)) as usize;
+                    Complex::<$t>::twiddle(jj,2 * n,inverse)
+                }).collect();
+                let mut a = vec![Complex::<$t>::zero(); m];
+                for j in 0..n {
+                    a[j] = data[j] * chirp[j];
+                }
+                let mut b = vec![Complex::<$t>::zero(); m];
+                b[0] = chirp[0].conj();
+                for j in 1..n {
+                    b[j] = chirp[j].conj();
+                    b[m - j] = chirp[j].conj();
+                }
+                Complex::<$t>::radix2(&mut a,false);
+                Complex::<$t>::radix2(&mut b,false);
+                for j in 0..m {
+                    a[j] *= b[j];
+                }
+                Complex::<$t>::radix2(&mut a,true);
+                let s = 1.0 / m as $t;
+                for k in 0..n {
+                    data[k] = a[k] * chirp[k] * s;
+                }
+            }
+        }
+    );
+);
+
+impl_fft!(f32);
+impl_fft!(f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn dft(data: &[f64c],inverse: bool) -> Vec<f64c> {
+        let n = data.len();
+        let sign = if inverse {
+            1.0
+        }
+        else {
+            -1.0
+        };
+        (0..n).map(|k| data.iter().enumerate().fold(f64c::zero(),|sum,(j,x)| {
+            let a = sign * 2.0 * std::f64::consts::PI * ((j * k) % n) as f64 / n as f64;
+            sum + *x * f64c::from_polar(1.0,a)
+        })).collect()
+    }
+
+    fn signal(n: usize) -> Vec<f64c> {
+        (0..n).map(|j| f64c::new((j as f64 * 0.7).sin() + 0.1 * j as f64,(j as f64 * 1.3).cos())).collect()
+    }
+
+    fn close(a: &[f64c],b: &[f64c]) -> bool {
+        a.iter().zip(b.iter()).all(|(x,y)| (*x - *y).abs() < 1e-9 * (1.0 + y.abs()))
+    }
+
+    // covers radix-2, mixed radix (6, 12, 35, 49, 60) and Bluestein (11, 13, 22, 37)
+    #[test]
+    fn matches_dft() {
+        for n in [1,2,3,4,5,6,7,8,9,11,12,13,16,22,35,37,49,60].iter() {
+            let x = signal(*n);
+            let mut y = x.clone();
+            f64c::fft(&mut y);
+            assert!(close(&y,&dft(&x,false)),"fft {}",n);
+            f64c::ifft(&mut y);
+            assert!(close(&y,&x),"ifft {}",n);
+        }
+    }
+
+    #[test]
+    fn rfft_matches_fft() {
+        for n in [6,9,10,15,16].iter() {
+            let x: Vec<f64> = (0..*n).map(|j| (j as f64 * 0.9).cos() + 0.2 * j as f64).collect();
+            let spectrum = f64c::rfft(&x);
+            let full = dft(&x.iter().map(|v| f64c::new(*v,0.0)).collect::<Vec<f64c>>(),false);
+            assert!(close(&spectrum,&full[0..n / 2 + 1]),"rfft {}",n);
+            let back = f64c::irfft(&spectrum,*n);
+            assert!(back.iter().zip(x.iter()).all(|(a,b)| (a - b).abs() < 1e-9),"irfft {}",n);
+        }
+    }
+
+    #[test]
+    fn fft2_round_trip() {
+        let x = signal(6 * 5);
+        let mut y = x.clone();
+        f64c::fft2(&mut y,6,5);
+        f64c::ifft2(&mut y,6,5);
+        assert!(close(&y,&x));
+    }
+
+    #[test]
+    fn fft2_empty() {
+        let mut data: Vec<f64c> = Vec::new();
+        f64c::fft2(&mut data,0,4);
+        f64c::fft2(&mut data,4,0);
+    }
+
+    #[test]
+    #[should_panic(expected = "fft2: a 4x4 transform needs 16 values, data holds 15")]
+    fn fft2_short_data() {
+        let mut data = signal(15);
+        f64c::fft2(&mut data,4,4);
+    }
+}
@@ -0,0 +1,463 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// HSV, HSL and HWB color models
+
+use std::{cmp,fmt};
+use crate::*;
+
+// Hues are in degrees and kept in [0,360), the other components are in [0,1].
+// Gray colors get hue 0.
+
+// which way around the hue circle to interpolate, as in CSS Color 4
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum HueDirection {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+#[derive(Copy,Clone)]
+pub struct HSV<T> {
+    pub h: T,
+    pub s: T,
+    pub v: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct HSVA<T> {
+    pub h: T,
+    pub s: T,
+    pub v: T,
+    pub a: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct HSL<T> {
+    pub h: T,
+    pub s: T,
+    pub l: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct HSLA<T> {
+    pub h: T,
+    pub s: T,
+    pub l: T,
+    pub a: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct HWB<T> {
+    pub h: T,
+    pub w: T,
+    pub b: T,
+}
+
+#[derive(Copy,Clone)]
+pub struct HWBA<T> {
+    pub h: T,
+    pub w: T,
+    pub b: T,
+    pub a: T,
+}
+
+macro_rules! impl_hue (
+    ($t:ty) => (
+        impl HSV<$t> {
+            pub fn wrap_hue(h: $t) -> $t {
+                let h = h.rem_euclid(360.0);
+                // rem_euclid can round up to exactly 360 for tiny negative hues
+                if h >= 360.0 {
+                    0.0
+                }
+                else {
+                    h
+                }
+            }
+
+            pub fn lerp_hue(a: $t,b: $t,t: $t,dir: HueDirection) -> $t {
+                let a = HSV::<$t>::wrap_hue(a);
+                let b = HSV::<$t>::wrap_hue(b);
+                let mut d = b - a;
+                match dir {
+                    HueDirection::Shorter => if d > 180.0 {
+                        d -= 360.0;
+                    }
+                    else if d < -180.0 {
+                        d += 360.0;
+                    },
+                    HueDirection::Longer => if (d > 0.0) && (d < 180.0) {
+                        d -= 360.0;
+                    }
+                    else if (d > -180.0) && (d <= 0.0) {
+                        d += 360.0;
+                    },
+                    HueDirection::Increasing => if d < 0.0 {
+                        d += 360.0;
+                    },
+                    HueDirection::Decreasing => if d > 0.0 {
+                        d -= 360.0;
+                    },
+                }
+                HSV::<$t>::wrap_hue(a + d * t)
+            }
+
+            // hue and the largest and smallest component of a color
+            fn hue_max_min(c: &RGB<$t>) -> ($t,$t,$t) {
+                let max = c.r.max(c.g).max(c.b);
+                let min = c.r.min(c.g).min(c.b);
+                let d = max - min;
+                let h = if d == 0.0 {
+                    0.0
+                }
+                else if max == c.r {
+                    60.0 * ((c.g - c.b) / d)
+                }
+                else if max == c.g {
+                    60.0 * ((c.b - c.r) / d + 2.0)
+                }
+                else {
+                    60.0 * ((c.r - c.g) / d + 4.0)
+                };
+                (HSV::<$t>::wrap_hue(h),max,min)
+            }
+        }
+
+        impl From<RGB<$t>> for HSV<$t> {
+            fn from(c: RGB<$t>) -> HSV<$t> {
+                let (h,max,min) = HSV::<$t>::hue_max_min(&c);
+                HSV {
+                    h: h,
+                    s: if max > 0.0 {
+                        (max - min) / max
+                    }
+                    else {
+                        0.0
+                    },
+                    v: max,
+                }
+            }
+        }
+
+        impl From<HSV<$t>> for RGB<$t> {
+            fn from(c: HSV<$t>) -> RGB<$t> {
+                let f = |n: $t| {
+                    let k = (n + c.h / 60.0).rem_euclid(6.0);
+                    c.v - c.v * c.s * k.min(4.0 - k).clamp(0.0,1.0)
+                };
+                RGB {
+                    r: f(5.0),
+                    g: f(3.0),
+                    b: f(1.0),
+                }
+            }
+        }
+
+        impl From<RGB<$t>> for HSL<$t> {
+            fn from(c: RGB<$t>) -> HSL<$t> {
+                let (h,max,min) = HSV::<$t>::hue_max_min(&c);
+                let l = 0.5 * (max + min);
+                let d = 1.0 - (2.0 * l - 1.0).abs();
+                HSL {
+                    h: h,
+                    s: if d > 0.0 {
+                        (max - min) / d
+                    }
+                    else {
+                        0.0
+                    },
+                    l: l,
+                }
+            }
+        }
+
+        impl From<HSL<$t>> for RGB<$t> {
+            fn from(c: HSL<$t>) -> RGB<$t> {
+                let a = c.s * c.l.min(1.0 - c.l);
+                let f = |n: $t| {
+                    let k = (n + c.h / 30.0).rem_euclid(12.0);
+                    c.l - a * (k - 3.0).min(9.0 - k).clamp(-1.0,1.0)
+                };
+                RGB {
+                    r: f(0.0),
+                    g: f(8.0),
+                    b: f(4.0),
+                }
+            }
+        }
+
+        impl From<RGB<$t>> for HWB<$t> {
+            fn from(c: RGB<$t>) -> HWB<$t> {
+                let (h,max,min) = HSV::<$t>::hue_max_min(&c);
+                HWB {
+                    h: h,
+                    w: min,
+                    b: 1.0 - max,
+                }
+            }
+        }
+
+        impl From<HWB<$t>> for RGB<$t> {
+            fn from(c: HWB<$t>) -> RGB<$t> {
+                if c.w + c.b >= 1.0 {
+                    let gray = c.w / (c.w + c.b);
+                    return RGB {
+                        r: gray,
+                        g: gray,
+                        b: gray,
+                    };
+                }
+                let pure = RGB::<$t>::from(HSV {
+                    h: c.h,
+                    s: 1.0,
+                    v: 1.0,
+                });
+                let k = 1.0 - c.w - c.b;
+                RGB {
+                    r: pure.r * k + c.w,
+                    g: pure.g * k + c.w,
+                    b: pure.b * k + c.w,
+                }
+            }
+        }
+
+        impl_hue_model!(HSV,HSVA,s,v,$t);
+        impl_hue_model!(HSL,HSLA,s,l,$t);
+        impl_hue_model!(HWB,HWBA,w,b,$t);
+    );
+);
+
+// everything a hue model and its alpha variant share, given the RGB conversions
+macro_rules! impl_hue_model (
+    ($n:ident,$na:ident,$p:ident,$q:ident,$t:ty) => (
+        impl $n<$t> {
+            pub fn new(h: $t,$p: $t,$q: $t) -> $n<$t> {
+                $n {
+                    h: HSV::<$t>::wrap_hue(h),
+                    $p: $p,
+                    $q: $q,
+                }
+            }
+
+            pub fn lerp(&self,other: &$n<$t>,t: $t,dir: HueDirection) -> $n<$t> {
+                $n {
+                    h: HSV::<$t>::lerp_hue(self.h,other.h,t,dir),
+                    $p: self.$p + (other.$p - self.$p) * t,
+                    $q: self.$q + (other.$q - self.$q) * t,
+                }
+            }
+        }
+
+        impl $na<$t> {
+            pub fn new(h: $t,$p: $t,$q: $t,a: $t) -> $na<$t> {
+                $na {
+                    h: HSV::<$t>::wrap_hue(h),
+                    $p: $p,
+                    $q: $q,
+                    a: a,
+                }
+            }
+
+            pub fn lerp(&self,other: &$na<$t>,t: $t,dir: HueDirection) -> $na<$t> {
+                $na {
+                    h: HSV::<$t>::lerp_hue(self.h,other.h,t,dir),
+                    $p: self.$p + (other.$p - self.$p) * t,
+                    $q: self.$q + (other.$q - self.$q) * t,
+                    a: self.a + (other.a - self.a) * t,
+                }
+            }
+        }
+
+        impl From<RGBA<$t>> for $na<$t> {
+            fn from(c: RGBA<$t>) -> $na<$t> {
+                let m = $n::<$t>::from(RGB {
+                    r: c.r,
+                    g: c.g,
+                    b: c.b,
+                });
+                $na {
+                    h: m.h,
+                    $p: m.$p,
+                    $q: m.$q,
+                    a: c.a,
+                }
+            }
+        }
+
+        impl From<$na<$t>> for RGBA<$t> {
+            fn from(c: $na<$t>) -> RGBA<$t> {
+                let rgb = RGB::<$t>::from($n {
+                    h: c.h,
+                    $p: c.$p,
+                    $q: c.$q,
+                });
+                RGBA {
+                    r: rgb.r,
+                    g: rgb.g,
+                    b: rgb.b,
+                    a: c.a,
+                }
+            }
+        }
+
+        impl cmp::PartialEq for $n<$t> {
+            fn eq(&self,other: &$n<$t>) -> bool {
+                (self.h == other.h)
+                && (self.$p == other.$p)
+                && (self.$q == other.$q)
+            }
+        }
+
+        impl cmp::PartialEq for $na<$t> {
+            fn eq(&self,other: &$na<$t>) -> bool {
+                (self.h == other.h)
+                && (self.$p == other.$p)
+                && (self.$q == other.$q)
+                && (self.a == other.a)
+            }
+        }
+
+        impl fmt::Display for $n<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.h,self.$p,self.$q)
+            }
+        }
+
+        impl fmt::Debug for $n<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.h,self.$p,self.$q)
+            }
+        }
+
+        impl fmt::Display for $na<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{},{})",self.h,self.$p,self.$q,self.a)
+            }
+        }
+
+        impl fmt::Debug for $na<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{},{})",self.h,self.$p,self.$q,self.a)
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type f32hsv = HSV<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32hsva = HSVA<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32hsl = HSL<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32hsla = HSLA<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32hwb = HWB<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32hwba = HWBA<f32>;
+
+impl_hue!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64hsv = HSV<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64hsva = HSVA<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64hsl = HSL<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64hsla = HSLA<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64hwb = HWB<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64hwba = HWBA<f64>;
+
+impl_hue!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64rgb,b: f64rgb) -> bool {
+        ((a.r - b.r).abs() < 1e-12) && ((a.g - b.g).abs() < 1e-12) && ((a.b - b.b).abs() < 1e-12)
+    }
+
+    #[test]
+    fn round_trip() {
+        // an 11x11x11 grid, which includes the grays, the primaries and black and white
+        for r in 0..=10 {
+            for g in 0..=10 {
+                for b in 0..=10 {
+                    let c = f64rgb::new(r as f64 / 10.0,g as f64 / 10.0,b as f64 / 10.0);
+                    assert!(close(f64rgb::from(f64hsv::from(c)),c),"hsv {}",c);
+                    assert!(close(f64rgb::from(f64hsl::from(c)),c),"hsl {}",c);
+                    assert!(close(f64rgb::from(f64hwb::from(c)),c),"hwb {}",c);
+                }
+            }
+        }
+        let a = f64rgba::new(0.2,0.4,0.6,0.5);
+        let b = f64rgba::from(f64hsla::from(a));
+        assert!(close(f64rgb::new(b.r,b.g,b.b),f64rgb::new(a.r,a.g,a.b)) && (b.a == 0.5));
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(f64hsv::from(f64rgb::new(1.0,0.0,0.0)),f64hsv::new(0.0,1.0,1.0));
+        assert_eq!(f64hsv::from(f64rgb::new(0.0,1.0,0.0)),f64hsv::new(120.0,1.0,1.0));
+        assert_eq!(f64hsv::from(f64rgb::new(0.0,0.0,1.0)),f64hsv::new(240.0,1.0,1.0));
+        assert_eq!(f64hsl::from(f64rgb::new(0.5,0.5,0.5)),f64hsl::new(0.0,0.0,0.5));
+        assert_eq!(f64hwb::from(f64rgb::new(1.0,1.0,0.0)),f64hwb::new(60.0,0.0,0.0));
+        // whiteness and blackness adding up to more than 1 give a gray
+        assert!(close(f64rgb::from(f64hwb::new(90.0,0.6,0.6)),f64rgb::new(0.5,0.5,0.5)));
+    }
+
+    #[test]
+    fn wrap_hue() {
+        assert_eq!(f64hsv::wrap_hue(0.0),0.0);
+        assert_eq!(f64hsv::wrap_hue(360.0),0.0);
+        assert_eq!(f64hsv::wrap_hue(725.0),5.0);
+        assert_eq!(f64hsv::wrap_hue(-90.0),270.0);
+        assert_eq!(f64hsv::wrap_hue(-720.0),0.0);
+        assert_eq!(f64hsv::wrap_hue(-1e-20),0.0);
+        assert_eq!(f32hsv::wrap_hue(-1e-10),0.0);
+        assert!(f64hsv::wrap_hue(359.999) < 360.0);
+    }
+
+    #[test]
+    fn lerp_hue() {
+        // (a,b,direction,hue halfway)
+        let cases = [
+            (350.0,10.0,HueDirection::Shorter,0.0),
+            (10.0,350.0,HueDirection::Shorter,0.0),
+            (350.0,10.0,HueDirection::Longer,180.0),
+            (10.0,350.0,HueDirection::Longer,180.0),
+            (350.0,10.0,HueDirection::Increasing,0.0),
+            (10.0,350.0,HueDirection::Increasing,180.0),
+            (350.0,10.0,HueDirection::Decreasing,180.0),
+            (10.0,350.0,HueDirection::Decreasing,0.0),
+            (30.0,30.0,HueDirection::Shorter,30.0),
+            (30.0,30.0,HueDirection::Longer,210.0),
+            (30.0,30.0,HueDirection::Increasing,30.0),
+            (30.0,30.0,HueDirection::Decreasing,30.0),
+            (0.0,180.0,HueDirection::Shorter,90.0),
+            (-10.0,370.0,HueDirection::Shorter,0.0),
+        ];
+        for &(a,b,dir,expected) in cases.iter() {
+            let h = f64hsv::lerp_hue(a,b,0.5,dir);
+            assert!((h - expected).abs() < 1e-12,"{} {} {:?}: {}",a,b,dir,h);
+            assert!((f64hsv::lerp_hue(a,b,0.0,dir) - f64hsv::wrap_hue(a)).abs() < 1e-12);
+            let end = f64hsv::lerp_hue(a,b,1.0,dir);
+            assert!((end - f64hsv::wrap_hue(b)).abs() < 1e-12,"{} {} {:?}: {}",a,b,dir,end);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Pixel implementation for the image crate
+
+use std::{ops,slice};
+use image::Pixel;
+use crate::*;
+
+// RGB and RGBA are repr(C), so their components can be viewed as a slice.
+// Luma conversion, inversion and blending are left to the image crate's own
+// Rgb and Rgba types.
+macro_rules! impl_image_pixel (
+    ($t:ty) => (
+        impl From<image::Rgb<$t>> for RGB<$t> {
+            fn from(p: image::Rgb<$t>) -> RGB<$t> {
+                RGB {
+                    r: p.0[0],
+                    g: p.0[1],
+                    b: p.0[2],
+                }
+            }
+        }
+
+        impl From<RGB<$t>> for image::Rgb<$t> {
+            fn from(c: RGB<$t>) -> image::Rgb<$t> {
+                image::Rgb([c.r,c.g,c.b])
+            }
+        }
+
+        impl From<image::Rgba<$t>> for RGBA<$t> {
+            fn from(p: image::Rgba<$t>) -> RGBA<$t> {
+                RGBA {
+                    r: p.0[0],
+                    g: p.0[1],
+                    b: p.0[2],
+                    a: p.0[3],
+                }
+            }
+        }
+
+        impl From<RGBA<$t>> for image::Rgba<$t> {
+            fn from(c: RGBA<$t>) -> image::Rgba<$t> {
+                image::Rgba([c.r,c.g,c.b,c.a])
+            }
+        }
+
+        impl Pixel for RGB<$t> {
+            type Subpixel = $t;
+            const CHANNEL_COUNT: u8 = 3;
+            const COLOR_MODEL: &'static str = "RGB";
+
+            fn channels(&self) -> &[$t] {
+                unsafe { slice::from_raw_parts(self as *const RGB<$t> as *const $t,3) }
+            }
+
+            fn channels_mut(&mut self) -> &mut [$t] {
+                unsafe { slice::from_raw_parts_mut(self as *mut RGB<$t> as *mut $t,3) }
+            }
+
+            fn channels4(&self) -> ($t,$t,$t,$t) {
+                (self.r,self.g,self.b,<$t as image::Primitive>::DEFAULT_MAX_VALUE)
+            }
+
+            fn from_channels(a: $t,b: $t,c: $t,_d: $t) -> RGB<$t> {
+                RGB {
+                    r: a,
+                    g: b,
+                    b: c,
+                }
+            }
+
+            fn from_slice(slice: &[$t]) -> &RGB<$t> {
+                assert_eq!(slice.len(),3);
+                unsafe { &*(slice.as_ptr() as *const RGB<$t>) }
+            }
+
+            fn from_slice_mut(slice: &mut [$t]) -> &mut RGB<$t> {
+                assert_eq!(slice.len(),3);
+                unsafe { &mut *(slice.as_mut_ptr() as *mut RGB<$t>) }
+            }
+
+            fn to_rgb(&self) -> image::Rgb<$t> {
+                image::Rgb::<$t>::from(*self)
+            }
+
+            fn to_rgba(&self) -> image::Rgba<$t> {
+                image::Rgb::<$t>::from(*self).to_rgba()
+            }
+
+            fn to_luma(&self) -> image::Luma<$t> {
+                image::Rgb::<$t>::from(*self).to_luma()
+            }
+
+            fn to_luma_alpha(&self) -> image::LumaA<$t> {
+                image::Rgb::<$t>::from(*self).to_luma_alpha()
+            }
+
+            fn map<F>(&self,f: F) -> RGB<$t> where F: FnMut($t) -> $t {
+                let mut c = *self;
+                c.apply(f);
+                c
+            }
+
+            fn apply<F>(&mut self,mut f: F) where F: FnMut($t) -> $t {
+                self.r = f(self.r);
+                self.g = f(self.g);
+                self.b = f(self.b);
+            }
+
+            fn map_with_alpha<F,G>(&self,f: F,g: G) -> RGB<$t> where F: FnMut($t) -> $t,G: FnMut($t) -> $t {
+                let mut c = *self;
+                c.apply_with_alpha(f,g);
+                c
+            }
+
+            fn apply_with_alpha<F,G>(&mut self,f: F,_g: G) where F: FnMut($t) -> $t,G: FnMut($t) -> $t {
+                self.apply(f);
+            }
+
+            fn map2<F>(&self,other: &RGB<$t>,f: F) -> RGB<$t> where F: FnMut($t,$t) -> $t {
+                let mut c = *self;
+                c.apply2(other,f);
+                c
+            }
+
+            fn apply2<F>(&mut self,other: &RGB<$t>,mut f: F) where F: FnMut($t,$t) -> $t {
+                self.r = f(self.r,other.r);
+                self.g = f(self.g,other.g);
+                self.b = f(self.b,other.b);
+            }
+
+            fn invert(&mut self) {
+                let mut p = image::Rgb::<$t>::from(*self);
+                p.invert();
+                *self = RGB::<$t>::from(p);
+            }
+
+            fn blend(&mut self,other: &RGB<$t>) {
+                let mut p = image::Rgb::<$t>::from(*self);
+                p.blend(&image::Rgb::<$t>::from(*other));
+                *self = RGB::<$t>::from(p);
+            }
+        }
+
+        impl Pixel for RGBA<$t> {
+            type Subpixel = $t;
+            const CHANNEL_COUNT: u8 = 4;
+            const COLOR_MODEL: &'static str = "RGBA";
+            const HAS_ALPHA: bool = true;
+
+            fn channels(&self) -> &[$t] {
+                unsafe { slice::from_raw_parts(self as *const RGBA<$t> as *const $t,4) }
+            }
+
+            fn channels_mut(&mut self) -> &mut [$t] {
+                unsafe { slice::from_raw_parts_mut(self as *mut RGBA<$t> as *mut $t,4) }
+            }
+
+            fn alpha(&self) -> $t {
+                self.a
+            }
+
+            fn channels4(&self) -> ($t,$t,$t,$t) {
+                (self.r,self.g,self.b,self.a)
+            }
+
+            fn from_channels(a: $t,b: $t,c: $t,d: $t) -> RGBA<$t> {
+                RGBA {
+                    r: a,
+                    g: b,
+                    b: c,
+                    a: d,
+                }
+            }
+
+            fn from_slice(slice: &[$t]) -> &RGBA<$t> {
+                assert_eq!(slice.len(),4);
+                unsafe { &*(slice.as_ptr() as *const RGBA<$t>) }
+            }
+
+            fn from_slice_mut(slice: &mut [$t]) -> &mut RGBA<$t> {
+                assert_eq!(slice.len(),4);
+                unsafe { &mut *(slice.as_mut_ptr() as *mut RGBA<$t>) }
+            }
+
+            fn to_rgb(&self) -> image::Rgb<$t> {
+                image::Rgba::<$t>::from(*self).to_rgb()
+            }
+
+            fn to_rgba(&self) -> image::Rgba<$t> {
+                image::Rgba::<$t>::from(*self)
+            }
+
+            fn to_luma(&self) -> image::Luma<$t> {
+                image::Rgba::<$t>::from(*self).to_luma()
+            }
+
+            fn to_luma_alpha(&self) -> image::LumaA<$t> {
+                image::Rgba::<$t>::from(*self).to_luma_alpha()
+            }
+
+            fn map<F>(&self,f: F) -> RGBA<$t> where F: FnMut($t) -> $t {
+                let mut c = *self;
+                c.apply(f);
+                c
+            }
+
+            fn apply<F>(&mut self,mut f: F) where F: FnMut($t) -> $t {
+                self.r = f(self.r);
+                self.g = f(self.g);
+                self.b = f(self.b);
+                self.a = f(self.a);
+            }
+
+            fn map_with_alpha<F,G>(&self,f: F,g: G) -> RGBA<$t> where F: FnMut($t) -> $t,G: FnMut($t) -> $t {
+                let mut c = *self;
+                c.apply_with_alpha(f,g);
+                c
+            }
+
+            fn apply_with_alpha<F,G>(&mut self,mut f: F,mut g: G) where F: FnMut($t) -> $t,G: FnMut($t) -> $t {
+                self.r = f(self.r);
+                self.g = f(self.g);
+                self.b = f(self.b);
+                self.a = g(self.a);
+            }
+
+            fn map2<F>(&self,other: &RGBA<$t>,f: F) -> RGBA<$t> where F: FnMut($t,$t) -> $t {
+                let mut c = *self;
+                c.apply2(other,f);
+                c
+            }
+
+            fn apply2<F>(&mut self,other: &RGBA<$t>,mut f: F) where F: FnMut($t,$t) -> $t {
+                self.r = f(self.r,other.r);
+                self.g = f(self.g,other.g);
+                self.b = f(self.b,other.b);
+                self.a = f(self.a,other.a);
+            }
+
+            fn invert(&mut self) {
+                let mut p = image::Rgba::<$t>::from(*self);
+                p.invert();
+                *self = RGBA::<$t>::from(p);
+            }
+
+            fn blend(&mut self,other: &RGBA<$t>) {
+                let mut p = image::Rgba::<$t>::from(*self);
+                p.blend(&image::Rgba::<$t>::from(*other));
+                *self = RGBA::<$t>::from(p);
+            }
+        }
+
+        impl RGBA<$t> {
+            // pixels of any buffer with $t subpixels, row by row
+            pub fn from_image_buffer<P,C>(buffer: &image::ImageBuffer<P,C>) -> Vec<RGBA<$t>> where P: Pixel<Subpixel = $t>,C: ops::Deref<Target = [$t]> {
+                buffer.pixels().map(|p| RGBA::<$t>::from(p.to_rgba())).collect()
+            }
+
+            // None if pixels does not hold width x height pixels
+            pub fn to_image_buffer(width: u32,height: u32,pixels: &[RGBA<$t>]) -> Option<image::ImageBuffer<image::Rgba<$t>,Vec<$t>>> {
+                if pixels.len() as u64 != width as u64 * height as u64 {
+                    return None;
+                }
+                let data = pixels.iter().flat_map(|c| [c.r,c.g,c.b,c.a]).collect();
+                image::ImageBuffer::from_raw(width,height,data)
+            }
+        }
+    );
+);
+
+impl_image_pixel!(u8);
+impl_image_pixel!(u16);
+impl_image_pixel!(f32);
+impl_image_pixel!(f64);
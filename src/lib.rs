@@ -1,6 +1,8 @@
 // (C) Copyright 2020, by Germans Media Technology & Services
 // Math
 
+#![allow(clippy::redundant_field_names,clippy::too_many_arguments,clippy::excessive_precision)]
+
 pub trait Zero {
     fn zero() -> Self;
 }
@@ -54,6 +56,11 @@ impl One for isize { fn one() -> isize { 1 } }
 mod vector;
 pub use vector::*;
 
+mod swizzle;
+
+mod convert;
+pub use convert::*;
+
 mod matrix;
 pub use matrix::*;
 
@@ -63,9 +70,20 @@ pub use multivector::*;
 mod rectangle;
 pub use rectangle::*;
 
+mod region;
+pub use region::*;
+
+mod packer;
+pub use packer::*;
+
 mod complex;
 pub use complex::*;
 
+mod fft;
+
+mod polynomial;
+pub use polynomial::*;
+
 mod quaternion;
 pub use quaternion::*;
 
@@ -74,3 +92,23 @@ pub use euler::*;
 
 mod color;
 pub use color::*;
+
+mod pixel;
+pub use pixel::*;
+
+#[cfg(feature = "image")]
+mod image_pixel;
+
+mod transfer;
+pub use transfer::*;
+
+mod hsv;
+pub use hsv::*;
+
+mod colorspace;
+pub use colorspace::*;
+
+mod composite;
+pub use composite::*;
+
+mod tonemap;
@@ -223,32 +223,9 @@ macro_rules! impl_mat3x3 (
                 }
             }
 
+            // r is the scalar part, same as Mat3x3::from(Quaternion)
             pub fn rotate(r: $t,i: $t,j: $t,k: $t) -> Mat3x3<$t> {
-                let mut rr = r * r;
-                let mut ii = i * i;
-                let mut jj = j * j;
-                let kk = k * k;
-                let n = rr + ii + jj + kk;
-                let s = if n != 0.0 {
-                    2.0 / n
-                }
-                else {
-                    0.0
-                };
-                let kr = s * k * r;
-                rr *= s;
-                ii *= s;
-                let ki = s * k * i;
-                let ri = s * r * i;
-                let ij = s * i * j;
-                let kj = s * k * j;
-                let rj = s * r * j;
-                jj *= s;
-                Mat3x3 {
-                    x: XYZ::<$t>::new(1.0 - (ii + jj),ri - kj,rj + ki),
-                    y: XYZ::<$t>::new(ri + kj,1.0 - (rr + jj),ij - kr),
-                    z: XYZ::<$t>::new(rj - ki,ij + kr,1.0 - (rr + ii)),
-                }
+                Mat3x3::<$t>::from(Quaternion::<$t>::new(r,i,j,k))
             }
 
             pub fn pitch(a: $t) -> Mat3x3<$t> {
@@ -711,33 +688,9 @@ macro_rules! impl_mat4x4 (
                 }
             }
 
+            // r is the scalar part, same as Mat4x4::from(Quaternion)
             pub fn rotate(r: $t,i: $t,j: $t,k: $t) -> Mat4x4<$t> {
-                let mut rr = r * r;
-                let mut ii = i * i;
-                let mut jj = j * j;
-                let kk = k * k;
-                let n = rr + ii + jj + kk;
-                let s = if n != 0.0 {
-                    2.0 / n
-                }
-                else {
-                    0.0
-                };
-                let kr = s * k * r;
-                rr *= s;
-                ii *= s;
-                let ki = s * k * i;
-                let ri = s * r * i;
-                let ij = s * i * j;
-                let kj = s * k * j;
-                let rj = s * r * j;
-                jj *= s;
-                Mat4x4 {
-                    x: XYZW::<$t>::new(1.0 - (ii + jj),ri - kj,rj + ki,0.0),
-                    y: XYZW::<$t>::new(ri + kj,1.0 - (rr + jj),ij - kr,0.0),
-                    z: XYZW::<$t>::new(rj - ki,ij + kr,1.0 - (rr + ii),0.0),
-                    w: XYZW::<$t>::new(0.0,0.0,0.0,1.0),
-                }
+                Mat4x4::<$t>::from(Quaternion::<$t>::new(r,i,j,k))
             }
 
             pub fn pitch(a: $t) -> Mat4x4<$t> {
@@ -0,0 +1,449 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Rectangle packing
+
+use std::{error,fmt};
+use crate::*;
+
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum PackStrategy {
+    // bottom-left placement on a skyline of used heights, wasted space below
+    // the skyline and deallocated slots go to the free list
+    Skyline,
+    // maximal free rectangles, best short side fit
+    MaxRects,
+}
+
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum PackError {
+    // the requested size does not fit in the bin at all
+    TooLarge,
+    // there is no free space left for the requested size
+    Full,
+    // deallocate got a rectangle that is not an allocated slot
+    NotAllocated,
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackError::TooLarge => write!(f,"rectangle larger than bin"),
+            PackError::Full => write!(f,"bin full"),
+            PackError::NotAllocated => write!(f,"rectangle not allocated"),
+        }
+    }
+}
+
+impl error::Error for PackError { }
+
+// Hands out non-overlapping slots inside a bin of fixed size. Every slot
+// reserves padding extra pixels to its right and bottom, except where it
+// touches the bin edge. The settings are private because the skyline and
+// free list are built from them.
+pub struct Packer<T> {
+    size: XY<T>,
+    padding: T,
+    strategy: PackStrategy,
+    skyline: Vec<(T,T,T)>,  // x, y and width of each skyline segment
+    free: Vec<Rectangle<T>>,
+    allocated: Vec<Rectangle<T>>,
+}
+
+macro_rules! impl_packer (
+    ($t:ty) => (
+        impl Packer<$t> {
+            pub fn new(size: XY<$t>,padding: $t,strategy: PackStrategy) -> Packer<$t> {
+                let mut packer = Packer {
+                    size: size,
+                    padding: padding,
+                    strategy: strategy,
+                    skyline: Vec::new(),
+                    free: Vec::new(),
+                    allocated: Vec::new(),
+                };
+                packer.clear();
+                packer
+            }
+
+            pub fn size(&self) -> XY<$t> {
+                self.size
+            }
+
+            pub fn padding(&self) -> $t {
+                self.padding
+            }
+
+            pub fn strategy(&self) -> PackStrategy {
+                self.strategy
+            }
+
+            pub fn clear(&mut self) {
+                let XY { x: w,y: h, } = self.padded_size();
+                self.skyline.clear();
+                self.free.clear();
+                self.allocated.clear();
+                match self.strategy {
+                    PackStrategy::Skyline => self.skyline.push((0,0,w)),
+                    PackStrategy::MaxRects => self.free.push(Rectangle {
+                        o: XY { x: 0,y: 0, },
+                        s: XY { x: w,y: h, },
+                    }),
+                }
+            }
+
+            pub fn allocate(&mut self,size: XY<$t>) -> Result<Rectangle<$t>,PackError> {
+                if (size.x > self.size.x) || (size.y > self.size.y) {
+                    return Err(PackError::TooLarge);
+                }
+                if (size.x == 0) || (size.y == 0) {
+                    return Ok(Rectangle {
+                        o: XY { x: 0,y: 0, },
+                        s: size,
+                    });
+                }
+                let w = size.x.checked_add(self.padding).ok_or(PackError::TooLarge)?;
+                let h = size.y.checked_add(self.padding).ok_or(PackError::TooLarge)?;
+                let o = match self.find_free(w,h) {
+                    Some(o) => o,
+                    None => if self.strategy == PackStrategy::Skyline {
+                        self.place_skyline(w,h).ok_or(PackError::Full)?
+                    }
+                    else {
+                        return Err(PackError::Full);
+                    },
+                };
+                self.split_free(&Rectangle {
+                    o: o,
+                    s: XY { x: w,y: h, },
+                });
+                let r = Rectangle {
+                    o: o,
+                    s: size,
+                };
+                self.allocated.push(r);
+                Ok(r)
+            }
+
+            // give back a slot returned by allocate, anything else (including a
+            // slot that was already given back) is rejected and changes nothing
+            pub fn deallocate(&mut self,r: &Rectangle<$t>) -> Result<(),PackError> {
+                if r.is_empty() {
+                    return Ok(());
+                }
+                let index = self.allocated.iter().position(|a| a == r).ok_or(PackError::NotAllocated)?;
+                self.allocated.swap_remove(index);
+                if self.allocated.is_empty() {
+                    self.clear();
+                    return Ok(());
+                }
+                let r = Rectangle {
+                    o: r.o,
+                    s: XY { x: r.s.x + self.padding,y: r.s.y + self.padding, },
+                };
+                if (self.strategy == PackStrategy::Skyline) && self.lower_skyline(&r) {
+                    return Ok(());
+                }
+                self.free.push(r);
+                self.merge_free();
+                Ok(())
+            }
+
+            // the bin with padding, clamped to the largest value of $t
+            fn padded_size(&self) -> XY<$t> {
+                XY {
+                    x: self.size.x.saturating_add(self.padding),
+                    y: self.size.y.saturating_add(self.padding),
+                }
+            }
+
+            // best short side fit among the free rectangles
+            fn find_free(&self,w: $t,h: $t) -> Option<XY<$t>> {
+                let mut best: Option<(XY<$t>,$t,$t)> = None;
+                for f in self.free.iter() {
+                    if (f.s.x >= w) && (f.s.y >= h) {
+                        let dx = f.s.x - w;
+                        let dy = f.s.y - h;
+                        let short = dx.min(dy);
+                        let long = dx.max(dy);
+                        let better = match best {
+                            Some((_,bs,bl)) => (short < bs) || ((short == bs) && (long < bl)),
+                            None => true,
+                        };
+                        if better {
+                            best = Some((f.o,short,long));
+                        }
+                    }
+                }
+                best.map(|b| b.0)
+            }
+
+            // carve a placed rectangle out of every free rectangle it overlaps
+            fn split_free(&mut self,placed: &Rectangle<$t>) {
+                let mut pieces = Vec::new();
+                self.free.retain(|f| {
+                    let i = match f.intersect(placed) {
+                        Some(i) => i,
+                        None => return true,
+                    };
+                    let fx1 = f.o.x + f.s.x;
+                    let fy1 = f.o.y + f.s.y;
+                    let ix1 = i.o.x + i.s.x;
+                    let iy1 = i.o.y + i.s.y;
+                    if i.o.x > f.o.x {
+                        pieces.push(Rectangle { o: f.o,s: XY { x: i.o.x - f.o.x,y: f.s.y, }, });
+                    }
+                    if ix1 < fx1 {
+                        pieces.push(Rectangle { o: XY { x: ix1,y: f.o.y, },s: XY { x: fx1 - ix1,y: f.s.y, }, });
+                    }
+                    if i.o.y > f.o.y {
+                        pieces.push(Rectangle { o: f.o,s: XY { x: f.s.x,y: i.o.y - f.o.y, }, });
+                    }
+                    if iy1 < fy1 {
+                        pieces.push(Rectangle { o: XY { x: f.o.x,y: iy1, },s: XY { x: f.s.x,y: fy1 - iy1, }, });
+                    }
+                    false
+                });
+                self.free.extend(pieces);
+                self.prune_free();
+            }
+
+            // drop free rectangles that lie inside another one
+            fn prune_free(&mut self) {
+                let mut i = 0;
+                while i < self.free.len() {
+                    let contained = (0..self.free.len()).any(|j| (j != i) && self.free[j].contains_rect(&self.free[i]) && ((self.free[j] != self.free[i]) || (j < i)));
+                    if contained {
+                        self.free.swap_remove(i);
+                    }
+                    else {
+                        i += 1;
+                    }
+                }
+            }
+
+            // join free rectangles that share a full edge, until none are left
+            fn merge_free(&mut self) {
+                'outer: loop {
+                    for i in 0..self.free.len() {
+                        for j in (i + 1)..self.free.len() {
+                            let a = self.free[i];
+                            let b = self.free[j];
+                            let merged = if (a.o.x == b.o.x) && (a.s.x == b.s.x) && ((a.o.y + a.s.y == b.o.y) || (b.o.y + b.s.y == a.o.y)) {
+                                Some(Rectangle {
+                                    o: XY { x: a.o.x,y: a.o.y.min(b.o.y), },
+                                    s: XY { x: a.s.x,y: a.s.y + b.s.y, },
+                                })
+                            }
+                            else if (a.o.y == b.o.y) && (a.s.y == b.s.y) && ((a.o.x + a.s.x == b.o.x) || (b.o.x + b.s.x == a.o.x)) {
+                                Some(Rectangle {
+                                    o: XY { x: a.o.x.min(b.o.x),y: a.o.y, },
+                                    s: XY { x: a.s.x + b.s.x,y: a.s.y, },
+                                })
+                            }
+                            else {
+                                None
+                            };
+                            if let Some(m) = merged {
+                                self.free[i] = m;
+                                self.free.swap_remove(j);
+                                continue 'outer;
+                            }
+                        }
+                    }
+                    break;
+                }
+                self.prune_free();
+            }
+
+            // lowest placement on the skyline, leftmost if tied
+            fn place_skyline(&mut self,w: $t,h: $t) -> Option<XY<$t>> {
+                let XY { x: bw,y: bh, } = self.padded_size();
+                let mut best: Option<XY<$t>> = None;
+                for i in 0..self.skyline.len() {
+                    let x = self.skyline[i].0;
+                    if w > bw - x {
+                        break;
+                    }
+                    let mut y = 0;
+                    let mut covered = 0;
+                    let mut j = i;
+                    while (covered < w) && (j < self.skyline.len()) {
+                        y = y.max(self.skyline[j].1);
+                        covered += self.skyline[j].2;
+                        j += 1;
+                    }
+                    if covered < w {
+                        break;
+                    }
+                    if h > bh - y {
+                        continue;
+                    }
+                    if best.map_or(true,|b| y < b.y) {
+                        best = Some(XY { x: x,y: y, });
+                    }
+                }
+                let o = best?;
+
+                // space under the new segment becomes free space
+                for &(sx,sy,sw) in self.skyline.iter() {
+                    let x0 = sx.max(o.x);
+                    let x1 = (sx + sw).min(o.x + w);
+                    if (x1 > x0) && (sy < o.y) {
+                        self.free.push(Rectangle {
+                            o: XY { x: x0,y: sy, },
+                            s: XY { x: x1 - x0,y: o.y - sy, },
+                        });
+                    }
+                }
+                self.set_skyline(o.x,o.x + w,o.y + h);
+                self.merge_free();
+                Some(o)
+            }
+
+            // a freed slot with nothing on top of it lowers the skyline instead
+            fn lower_skyline(&mut self,r: &Rectangle<$t>) -> bool {
+                let x1 = r.o.x + r.s.x;
+                let top = r.o.y + r.s.y;
+                let on_top = self.skyline.iter()
+                    .filter(|s| (s.0 < x1) && (s.0 + s.2 > r.o.x))
+                    .all(|s| s.1 == top);
+                if on_top {
+                    self.set_skyline(r.o.x,x1,r.o.y);
+                }
+                on_top
+            }
+
+            // set the skyline height between x0 and x1, merging equal neighbours
+            fn set_skyline(&mut self,x0: $t,x1: $t,y: $t) {
+                let mut skyline = Vec::with_capacity(self.skyline.len() + 2);
+                for &(sx,sy,sw) in self.skyline.iter() {
+                    let sx1 = sx + sw;
+                    if sx < x0 {
+                        skyline.push((sx,sy,sx1.min(x0) - sx));
+                    }
+                    if (sx < x1) && (sx1 > x0) && (sx.max(x0) == x0) {
+                        skyline.push((x0,y,x1 - x0));
+                    }
+                    if sx1 > x1 {
+                        let s = sx.max(x1);
+                        skyline.push((s,sy,sx1 - s));
+                    }
+                }
+                let mut merged: Vec<($t,$t,$t)> = Vec::with_capacity(skyline.len());
+                for s in skyline {
+                    match merged.last_mut() {
+                        Some(m) if m.1 == s.1 => m.2 += s.2,
+                        _ => merged.push(s),
+                    }
+                }
+                self.skyline = merged;
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type u16packer = Packer<u16>;
+
+impl_packer!(u16);
+
+#[allow(non_camel_case_types)]
+pub type u32packer = Packer<u32>;
+
+impl_packer!(u32);
+
+#[allow(non_camel_case_types)]
+pub type u64packer = Packer<u64>;
+
+impl_packer!(u64);
+
+#[allow(non_camel_case_types)]
+pub type usizepacker = Packer<usize>;
+
+impl_packer!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [PackStrategy; 2] = [PackStrategy::Skyline,PackStrategy::MaxRects];
+
+    fn overlap(a: &u32r,b: &u32r) -> bool {
+        a.intersect(b).is_some()
+    }
+
+    #[test]
+    fn double_free() {
+        for &strategy in STRATEGIES.iter() {
+            let mut packer = u32packer::new(u32xy::new(64,64),0,strategy);
+            let a = packer.allocate(u32xy::new(10,10)).unwrap();
+            let b = packer.allocate(u32xy::new(10,10)).unwrap();
+            assert_eq!(packer.deallocate(&a),Ok(()));
+            assert_eq!(packer.deallocate(&a),Err(PackError::NotAllocated));
+            // b is still allocated, so the next slot must not overlap it
+            let c = packer.allocate(u32xy::new(20,20)).unwrap();
+            assert!(!overlap(&b,&c));
+            assert_eq!(packer.deallocate(&b),Ok(()));
+            assert_eq!(packer.deallocate(&c),Ok(()));
+            assert_eq!(packer.deallocate(&c),Err(PackError::NotAllocated));
+        }
+    }
+
+    #[test]
+    fn foreign_rect() {
+        for &strategy in STRATEGIES.iter() {
+            let mut packer = u32packer::new(u32xy::new(64,64),1,strategy);
+            let a = packer.allocate(u32xy::new(10,10)).unwrap();
+            let outside = u32r::new(u32xy::new(100,100),u32xy::new(10,10));
+            let inside = u32r::new(u32xy::new(a.o.x,a.o.y),u32xy::new(5,5));
+            assert_eq!(packer.deallocate(&outside),Err(PackError::NotAllocated));
+            assert_eq!(packer.deallocate(&inside),Err(PackError::NotAllocated));
+            // nothing was freed, so a full size slot must not overlap a
+            let b = packer.allocate(u32xy::new(50,50)).unwrap();
+            assert!(!overlap(&a,&b));
+            let empty = u32r::new(u32xy::new(0,0),u32xy::new(0,5));
+            assert_eq!(packer.deallocate(&empty),Ok(()));
+        }
+    }
+
+    #[test]
+    fn padding_overflow() {
+        for &strategy in STRATEGIES.iter() {
+            let mut packer = u16packer::new(u16xy::new(65535,65535),1,strategy);
+            assert_eq!(packer.size(),u16xy::new(65535,65535));
+            let r = packer.allocate(u16xy::new(100,100)).unwrap();
+            assert!(r.o.x as u32 + r.s.x as u32 <= 65535);
+            let mut packer = u16packer::new(u16xy::new(65000,100),1000,strategy);
+            assert_eq!(packer.allocate(u16xy::new(65000,10)),Err(PackError::TooLarge));
+            assert_eq!(packer.padding(),1000);
+            assert_eq!(packer.strategy(),strategy);
+            let r = packer.allocate(u16xy::new(64000,10)).unwrap();
+            assert!(r.o.x as u32 + r.s.x as u32 <= 65000);
+        }
+    }
+
+    #[test]
+    fn slots_never_overlap() {
+        for &strategy in STRATEGIES.iter() {
+            let mut packer = u32packer::new(u32xy::new(128,128),2,strategy);
+            let mut slots: Vec<u32r> = Vec::new();
+            let mut state = 12345u32;
+            for _ in 0..500 {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                let n = state >> 16;
+                if n.is_multiple_of(3) && !slots.is_empty() {
+                    let r = slots.swap_remove((n as usize / 3) % slots.len());
+                    assert_eq!(packer.deallocate(&r),Ok(()));
+                }
+                else if let Ok(r) = packer.allocate(u32xy::new(1 + n % 23,1 + (n >> 5) % 17)) {
+                    assert!(r.o.x + r.s.x <= 128);
+                    assert!(r.o.y + r.s.y <= 128);
+                    for s in slots.iter() {
+                        assert!(!overlap(&r,s));
+                    }
+                    slots.push(r);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,425 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Integer colors and packed pixel formats
+
+use std::{cmp,fmt,hash};
+use crate::*;
+
+// Integer colors use the full range of their type, so 255 (or 65535) is 1.0.
+// Packed format names list the components from the most significant bits
+// down, RGB565 is rrrrrggggggbbbbb. Packing to fewer bits rounds to nearest,
+// unpacking scales back up so unpacking and packing again gives the original.
+
+macro_rules! impl_rgb_integer (
+    ($t:ty) => (
+        impl RGB<$t> {
+            pub fn new(r: $t,g: $t,b: $t) -> RGB<$t> {
+                RGB {
+                    r: r,
+                    g: g,
+                    b: b,
+                }
+            }
+        }
+
+        impl cmp::PartialEq for RGB<$t> {
+            fn eq(&self,other: &RGB<$t>) -> bool {
+                (self.r == other.r)
+                && (self.g == other.g)
+                && (self.b == other.b)
+            }
+        }
+
+        impl cmp::Eq for RGB<$t> { }
+
+        impl hash::Hash for RGB<$t> {
+            fn hash<H: hash::Hasher>(&self,state: &mut H) {
+                self.r.hash(state);
+                self.g.hash(state);
+                self.b.hash(state);
+            }
+        }
+
+        impl fmt::Display for RGB<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.r,self.g,self.b)
+            }
+        }
+
+        impl fmt::Debug for RGB<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{})",self.r,self.g,self.b)
+            }
+        }
+
+        impl Zero for RGB<$t> {
+            fn zero() -> RGB<$t> {
+                RGB {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                }
+            }
+        }
+    );
+);
+
+macro_rules! impl_rgba_integer (
+    ($t:ty) => (
+        impl RGBA<$t> {
+            pub fn new(r: $t,g: $t,b: $t,a: $t) -> RGBA<$t> {
+                RGBA {
+                    r: r,
+                    g: g,
+                    b: b,
+                    a: a,
+                }
+            }
+        }
+
+        impl cmp::PartialEq for RGBA<$t> {
+            fn eq(&self,other: &RGBA<$t>) -> bool {
+                (self.r == other.r)
+                && (self.g == other.g)
+                && (self.b == other.b)
+                && (self.a == other.a)
+            }
+        }
+
+        impl cmp::Eq for RGBA<$t> { }
+
+        impl hash::Hash for RGBA<$t> {
+            fn hash<H: hash::Hasher>(&self,state: &mut H) {
+                self.r.hash(state);
+                self.g.hash(state);
+                self.b.hash(state);
+                self.a.hash(state);
+            }
+        }
+
+        impl fmt::Display for RGBA<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{},{})",self.r,self.g,self.b,self.a)
+            }
+        }
+
+        impl fmt::Debug for RGBA<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"({},{},{},{})",self.r,self.g,self.b,self.a)
+            }
+        }
+
+        impl Zero for RGBA<$t> {
+            fn zero() -> RGBA<$t> {
+                RGBA {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                    a: 0,
+                }
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type u8rgb = RGB<u8>;
+
+impl_rgb_integer!(u8);
+
+#[allow(non_camel_case_types)]
+pub type u8rgba = RGBA<u8>;
+
+impl_rgba_integer!(u8);
+
+#[allow(non_camel_case_types)]
+pub type u16rgb = RGB<u16>;
+
+impl_rgb_integer!(u16);
+
+#[allow(non_camel_case_types)]
+pub type u16rgba = RGBA<u16>;
+
+impl_rgba_integer!(u16);
+
+// v out of 0..=from to 0..=to, rounded to nearest
+fn rescale(v: u32,from: u32,to: u32) -> u32 {
+    ((v as u64 * to as u64 + from as u64 / 2) / from as u64) as u32
+}
+
+impl RGBA<u8> {
+    pub fn to_rgba8888(&self) -> u32 {
+        ((self.r as u32) << 24) | ((self.g as u32) << 16) | ((self.b as u32) << 8) | (self.a as u32)
+    }
+
+    pub fn from_rgba8888(p: u32) -> RGBA<u8> {
+        RGBA {
+            r: (p >> 24) as u8,
+            g: (p >> 16) as u8,
+            b: (p >> 8) as u8,
+            a: p as u8,
+        }
+    }
+
+    pub fn to_bgra8888(&self) -> u32 {
+        ((self.b as u32) << 24) | ((self.g as u32) << 16) | ((self.r as u32) << 8) | (self.a as u32)
+    }
+
+    pub fn from_bgra8888(p: u32) -> RGBA<u8> {
+        RGBA {
+            r: (p >> 8) as u8,
+            g: (p >> 16) as u8,
+            b: (p >> 24) as u8,
+            a: p as u8,
+        }
+    }
+
+    pub fn to_rgba4444(&self) -> u16 {
+        ((rescale(self.r as u32,255,15) << 12)
+        | (rescale(self.g as u32,255,15) << 8)
+        | (rescale(self.b as u32,255,15) << 4)
+        | rescale(self.a as u32,255,15)) as u16
+    }
+
+    pub fn from_rgba4444(p: u16) -> RGBA<u8> {
+        let p = p as u32;
+        RGBA {
+            r: rescale((p >> 12) & 15,15,255) as u8,
+            g: rescale((p >> 8) & 15,15,255) as u8,
+            b: rescale((p >> 4) & 15,15,255) as u8,
+            a: rescale(p & 15,15,255) as u8,
+        }
+    }
+}
+
+impl RGB<u8> {
+    pub fn to_rgb565(&self) -> u16 {
+        ((rescale(self.r as u32,255,31) << 11)
+        | (rescale(self.g as u32,255,63) << 5)
+        | rescale(self.b as u32,255,31)) as u16
+    }
+
+    pub fn from_rgb565(p: u16) -> RGB<u8> {
+        let p = p as u32;
+        RGB {
+            r: rescale((p >> 11) & 31,31,255) as u8,
+            g: rescale((p >> 5) & 63,63,255) as u8,
+            b: rescale(p & 31,31,255) as u8,
+        }
+    }
+}
+
+impl RGBA<u16> {
+    pub fn to_rgb10a2(&self) -> u32 {
+        (rescale(self.r as u32,65535,1023) << 22)
+        | (rescale(self.g as u32,65535,1023) << 12)
+        | (rescale(self.b as u32,65535,1023) << 2)
+        | rescale(self.a as u32,65535,3)
+    }
+
+    pub fn from_rgb10a2(p: u32) -> RGBA<u16> {
+        RGBA {
+            r: rescale((p >> 22) & 1023,1023,65535) as u16,
+            g: rescale((p >> 12) & 1023,1023,65535) as u16,
+            b: rescale((p >> 2) & 1023,1023,65535) as u16,
+            a: rescale(p & 3,3,65535) as u16,
+        }
+    }
+}
+
+impl From<RGB<u8>> for RGB<u16> {
+    fn from(c: RGB<u8>) -> RGB<u16> {
+        RGB {
+            r: c.r as u16 * 257,
+            g: c.g as u16 * 257,
+            b: c.b as u16 * 257,
+        }
+    }
+}
+
+impl From<RGB<u16>> for RGB<u8> {
+    fn from(c: RGB<u16>) -> RGB<u8> {
+        RGB {
+            r: rescale(c.r as u32,65535,255) as u8,
+            g: rescale(c.g as u32,65535,255) as u8,
+            b: rescale(c.b as u32,65535,255) as u8,
+        }
+    }
+}
+
+impl From<RGBA<u8>> for RGBA<u16> {
+    fn from(c: RGBA<u8>) -> RGBA<u16> {
+        RGBA {
+            r: c.r as u16 * 257,
+            g: c.g as u16 * 257,
+            b: c.b as u16 * 257,
+            a: c.a as u16 * 257,
+        }
+    }
+}
+
+impl From<RGBA<u16>> for RGBA<u8> {
+    fn from(c: RGBA<u16>) -> RGBA<u8> {
+        RGBA {
+            r: rescale(c.r as u32,65535,255) as u8,
+            g: rescale(c.g as u32,65535,255) as u8,
+            b: rescale(c.b as u32,65535,255) as u8,
+            a: rescale(c.a as u32,65535,255) as u8,
+        }
+    }
+}
+
+// float components are clamped to [0,1] and rounded to nearest, NaN becomes 0
+macro_rules! impl_rgb_cast (
+    ($f:ty; $($i:ty),*) => (
+        $(
+            impl From<RGB<$i>> for RGB<$f> {
+                fn from(c: RGB<$i>) -> RGB<$f> {
+                    let m = <$i>::MAX as $f;
+                    RGB {
+                        r: c.r as $f / m,
+                        g: c.g as $f / m,
+                        b: c.b as $f / m,
+                    }
+                }
+            }
+
+            impl From<RGB<$f>> for RGB<$i> {
+                fn from(c: RGB<$f>) -> RGB<$i> {
+                    let m = <$i>::MAX as $f;
+                    RGB {
+                        r: (c.r.clamp(0.0,1.0) * m).round() as $i,
+                        g: (c.g.clamp(0.0,1.0) * m).round() as $i,
+                        b: (c.b.clamp(0.0,1.0) * m).round() as $i,
+                    }
+                }
+            }
+
+            impl From<RGBA<$i>> for RGBA<$f> {
+                fn from(c: RGBA<$i>) -> RGBA<$f> {
+                    let m = <$i>::MAX as $f;
+                    RGBA {
+                        r: c.r as $f / m,
+                        g: c.g as $f / m,
+                        b: c.b as $f / m,
+                        a: c.a as $f / m,
+                    }
+                }
+            }
+
+            impl From<RGBA<$f>> for RGBA<$i> {
+                fn from(c: RGBA<$f>) -> RGBA<$i> {
+                    let m = <$i>::MAX as $f;
+                    RGBA {
+                        r: (c.r.clamp(0.0,1.0) * m).round() as $i,
+                        g: (c.g.clamp(0.0,1.0) * m).round() as $i,
+                        b: (c.b.clamp(0.0,1.0) * m).round() as $i,
+                        a: (c.a.clamp(0.0,1.0) * m).round() as $i,
+                    }
+                }
+            }
+        )*
+    );
+);
+
+impl_rgb_cast!(f32; u8,u16);
+impl_rgb_cast!(f64; u8,u16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8888_round_trip() {
+        for v in 0..=255u8 {
+            let c = RGBA::<u8>::new(v,v ^ 0x5a,255 - v,v.wrapping_mul(37));
+            assert_eq!(RGBA::<u8>::from_rgba8888(c.to_rgba8888()),c);
+            assert_eq!(RGBA::<u8>::from_bgra8888(c.to_bgra8888()),c);
+        }
+        let c = RGBA::<u8>::new(0x12,0x34,0x56,0x78);
+        assert_eq!(c.to_rgba8888(),0x12345678);
+        assert_eq!(c.to_bgra8888(),0x56341278);
+    }
+
+    #[test]
+    fn rgba4444_round_trip() {
+        // every packed value survives unpacking and packing again
+        for p in 0..=65535u16 {
+            assert_eq!(RGBA::<u8>::from_rgba4444(p).to_rgba4444(),p);
+        }
+        // and every u8 packs to the nearest of the 16 levels
+        for v in 0..=255u8 {
+            let c = RGBA::<u8>::new(v,v,v,v);
+            let back = RGBA::<u8>::from_rgba4444(c.to_rgba4444());
+            assert!((back.r as i32 - v as i32).abs() <= 8,"{}",v);
+            assert_eq!(back,RGBA::<u8>::new(back.r,back.r,back.r,back.r));
+        }
+        assert_eq!(RGBA::<u8>::new(255,0,255,0).to_rgba4444(),0xf0f0);
+    }
+
+    #[test]
+    fn rgb565_round_trip() {
+        for p in 0..=65535u16 {
+            assert_eq!(RGB::<u8>::from_rgb565(p).to_rgb565(),p);
+        }
+        for v in 0..=255u8 {
+            let back = RGB::<u8>::from_rgb565(RGB::<u8>::new(v,v,v).to_rgb565());
+            assert!((back.r as i32 - v as i32).abs() <= 4,"{}",v);
+            assert!((back.g as i32 - v as i32).abs() <= 2,"{}",v);
+            assert!((back.b as i32 - v as i32).abs() <= 4,"{}",v);
+        }
+        assert_eq!(RGB::<u8>::new(255,255,255).to_rgb565(),0xffff);
+        assert_eq!(RGB::<u8>::new(0,255,0).to_rgb565(),0x07e0);
+    }
+
+    #[test]
+    fn rgb10a2_round_trip() {
+        for v in 0..1024u32 {
+            let p = (v << 22) | ((1023 - v) << 12) | ((v ^ 0x2aa) << 2) | (v & 3);
+            assert_eq!(RGBA::<u16>::from_rgb10a2(p).to_rgb10a2(),p);
+        }
+        assert_eq!(RGBA::<u16>::new(65535,0,65535,65535).to_rgb10a2(),0xffc00fff);
+        assert_eq!(RGBA::<u16>::from_rgb10a2(0xffffffff),RGBA::<u16>::new(65535,65535,65535,65535));
+    }
+
+    #[test]
+    fn rescale_u8_u16() {
+        for v in 0..=255u8 {
+            let c = RGBA::<u8>::new(v,v,v,v);
+            let wide = RGBA::<u16>::from(c);
+            assert_eq!(wide.r,v as u16 * 257);
+            assert_eq!(RGBA::<u8>::from(wide),c);
+            assert_eq!(RGB::<u8>::from(RGB::<u16>::from(RGB::<u8>::new(v,v,v))),RGB::<u8>::new(v,v,v));
+        }
+        // narrowing rounds to the nearest u8
+        for v in 0..=65535u16 {
+            let n = RGB::<u8>::from(RGB::<u16>::new(v,v,v)).r;
+            assert!((n as i32 * 257 - v as i32).abs() <= 128,"{}",v);
+        }
+    }
+
+    #[test]
+    fn float_conversion() {
+        assert_eq!(f32rgb::from(RGB::<u8>::new(0,0,255)),f32rgb::new(0.0,0.0,1.0));
+        assert_eq!(f64rgba::from(RGBA::<u16>::new(0,65535,0,65535)),f64rgba::new(0.0,1.0,0.0,1.0));
+        assert_eq!(RGB::<u8>::from(f32rgb::new(0.0,1.0,0.5)),RGB::<u8>::new(0,255,128));
+        assert_eq!(RGBA::<u16>::from(f64rgba::new(0.0,1.0,0.0,1.0)),RGBA::<u16>::new(0,65535,0,65535));
+        // the rounding boundary between 0 and 1
+        let below = (0.5 - 1e-4) / 255.0;
+        let above = (0.5 + 1e-4) / 255.0;
+        assert_eq!(RGB::<u8>::from(f32rgb::new(below,above,0.0)),RGB::<u8>::new(0,1,0));
+        assert_eq!(RGB::<u8>::from(f64rgb::new(below as f64,above as f64,0.0)),RGB::<u8>::new(0,1,0));
+        // clamped, NaN becomes 0
+        assert_eq!(RGBA::<u8>::from(f32rgba::new(-0.5,2.0,f32::NAN,1.0)),RGBA::<u8>::new(0,255,0,255));
+        // every integer value survives a trip through float
+        for v in 0..=255u8 {
+            let c = RGBA::<u8>::new(v,v,v,v);
+            assert_eq!(RGBA::<u8>::from(f32rgba::from(c)),c);
+        }
+        for v in 0..=65535u16 {
+            let c = RGB::<u16>::new(v,v,v);
+            assert_eq!(RGB::<u16>::from(f32rgb::from(c)),c);
+            assert_eq!(RGB::<u16>::from(f64rgb::from(c)),c);
+        }
+    }
+}
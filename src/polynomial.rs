@@ -0,0 +1,509 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Polynomials
+
+use std::{cmp,fmt,ops};
+use crate::*;
+
+// c[0] + c[1]x + c[2]x^2 + ..., without trailing zeros; the zero polynomial has no coefficients.
+// c is private so nothing can break that, root finding divides by the leading coefficient.
+#[derive(Clone)]
+pub struct Polynomial<T> {
+    c: Vec<T>,
+}
+
+macro_rules! impl_polynomial (
+    ($t:ty) => (
+        impl Polynomial<$t> {
+            pub fn new(c: Vec<$t>) -> Polynomial<$t> {
+                let mut p = Polynomial {
+                    c: c,
+                };
+                p.trim();
+                p
+            }
+
+            fn trim(&mut self) {
+                while let Some(&0.0) = self.c.last() {
+                    self.c.pop();
+                }
+            }
+
+            // lowest power first, without trailing zeros
+            pub fn coefficients(&self) -> &[$t] {
+                &self.c
+            }
+
+            pub fn degree(&self) -> usize {
+                if self.c.is_empty() {
+                    0
+                }
+                else {
+                    self.c.len() - 1
+                }
+            }
+
+            pub fn eval(&self,x: $t) -> $t {
+                self.c.iter().rev().fold(0.0,|acc,c| acc * x + c)
+            }
+
+            pub fn eval_complex(&self,z: Complex<$t>) -> Complex<$t> {
+                self.c.iter().rev().fold(Complex::<$t>::zero(),|acc,c| acc * z + *c)
+            }
+
+            pub fn derivative(&self) -> Polynomial<$t> {
+                Polynomial::<$t>::new(self.c.iter().enumerate().skip(1).map(|(n,c)| (n as $t) * c).collect())
+            }
+
+            // antiderivative with zero constant term
+            pub fn integral(&self) -> Polynomial<$t> {
+                let mut c = vec![0.0];
+                c.extend(self.c.iter().enumerate().map(|(n,c)| c / ((n + 1) as $t)));
+                Polynomial::<$t>::new(c)
+            }
+
+            // quotient and remainder; dividing by the zero polynomial gives (0,self)
+            pub fn div_rem(&self,divisor: &Polynomial<$t>) -> (Polynomial<$t>,Polynomial<$t>) {
+                if divisor.c.is_empty() || (self.c.len() < divisor.c.len()) {
+                    return (Polynomial::<$t>::zero(),self.clone());
+                }
+                let dn = divisor.c.len() - 1;
+                let lead = divisor.c[dn];
+                let mut r = self.c.clone();
+                let mut q = vec![0.0; self.c.len() - dn];
+                for n in (0..q.len()).rev() {
+                    let f = r[n + dn] / lead;
+                    q[n] = f;
+                    for (k,d) in divisor.c.iter().enumerate() {
+                        r[n + k] -= f * d;
+                    }
+                }
+                r.truncate(dn);
+                (Polynomial::<$t>::new(q),Polynomial::<$t>::new(r))
+            }
+
+            // All complex roots, with multiplicity. Above degree 2 the roots come from
+            // Durand-Kerner, which stops when no root moves by more than EPSILON
+            // (relative), or after 1000 rounds. There is no convergence flag. A
+            // root of multiplicity m is only accurate to about EPSILON^(1/m) of its
+            // size, so the roots of (x - 1)^4 are off by about 1e-2 in f32 and 1e-4
+            // in f64. Check with eval_complex if that matters.
+            pub fn roots(&self) -> Vec<Complex<$t>> {
+                match self.degree() {
+                    0 => Vec::new(),
+                    1 => vec![Complex::<$t>::new(-self.c[0] / self.c[1],0.0)],
+                    2 => Polynomial::<$t>::quadratic(self.c[2],self.c[1],self.c[0]),
+                    _ => self.durand_kerner(),
+                }
+            }
+
+            // Weierstrass iteration on all roots at once
+            fn durand_kerner(&self) -> Vec<Complex<$t>> {
+                let n = self.degree();
+                let lead = self.c[n];
+                let monic: Vec<$t> = self.c.iter().map(|c| c / lead).collect();
+                let radius = 1.0 + monic[0..n].iter().fold(0.0 as $t,|m,c| m.max(c.abs()));
+                let seed = Complex::<$t>::new(0.4,0.9);
+                let mut z: Vec<Complex<$t>> = (0..n).map(|k| seed.powf(k as $t) * radius).collect();
+                let eval = |x: Complex<$t>| monic.iter().rev().fold(Complex::<$t>::zero(),|acc,c| acc * x + *c);
+                for _ in 0..1000 {
+                    let mut change: $t = 0.0;
+                    for k in 0..n {
+                        let mut d = Complex::<$t>::one();
+                        for j in 0..n {
+                            if j != k {
+                                d *= z[k] - z[j];
+                            }
+                        }
+                        let step = eval(z[k]) / d;
+                        z[k] -= step;
+                        change = change.max(step.abs() / (1.0 + z[k].abs()));
+                    }
+                    if change <= <$t>::EPSILON {
+                        break;
+                    }
+                }
+                z
+            }
+
+            // roots of a x^2 + b x + c, avoiding cancellation between -b and the discriminant
+            pub fn quadratic(a: $t,b: $t,c: $t) -> Vec<Complex<$t>> {
+                if a == 0.0 {
+                    return if b == 0.0 {
+                        Vec::new()
+                    }
+                    else {
+                        vec![Complex::<$t>::new(-c / b,0.0)]
+                    };
+                }
+                let s = Complex::<$t>::new(b * b - 4.0 * a * c,0.0).sqrt();
+                let q = if b >= 0.0 {
+                    (b + s) * -0.5
+                }
+                else {
+                    (b - s) * -0.5
+                };
+                if (q.r == 0.0) && (q.i == 0.0) {
+                    vec![Complex::zero(),Complex::zero()]
+                }
+                else {
+                    vec![q / a,c / q]
+                }
+            }
+
+            // roots of a x^3 + b x^2 + c x + d, by Cardano's method
+            pub fn cubic(a: $t,b: $t,c: $t,d: $t) -> Vec<Complex<$t>> {
+                if a == 0.0 {
+                    return Polynomial::<$t>::quadratic(b,c,d);
+                }
+                let b = b / a;
+                let c = c / a;
+                let d = d / a;
+
+                // x = t - b / 3 gives t^3 + p t + q = 0
+                let p = c - b * b / 3.0;
+                let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+                let shift = Complex::<$t>::new(-b / 3.0,0.0);
+                let s = Complex::<$t>::new(0.25 * q * q + p * p * p / 27.0,0.0).sqrt();
+                let u1 = s - 0.5 * q;
+                let u2 = -s - 0.5 * q;
+                let u3 = if u1.abs() >= u2.abs() {
+                    u1
+                }
+                else {
+                    u2
+                };
+                if (u3.r == 0.0) && (u3.i == 0.0) {
+                    return vec![shift,shift,shift];
+                }
+                let u = u3.powf(1.0 / 3.0);
+                let w = Complex::<$t>::new(-0.5,0.5 * (3.0 as $t).sqrt());
+                let mut roots = Vec::with_capacity(3);
+                let mut uk = u;
+                for _ in 0..3 {
+                    roots.push(uk - p / (3.0 * uk) + shift);
+                    uk *= w;
+                }
+                roots
+            }
+
+            // roots of a x^4 + b x^3 + c x^2 + d x + e, by Ferrari's method
+            pub fn quartic(a: $t,b: $t,c: $t,d: $t,e: $t) -> Vec<Complex<$t>> {
+                if a == 0.0 {
+                    return Polynomial::<$t>::cubic(b,c,d,e);
+                }
+                let b = b / a;
+                let c = c / a;
+                let d = d / a;
+                let e = e / a;
+
+                // x = y - b / 4 gives y^4 + p y^2 + q y + r = 0
+                let bb = b * b;
+                let p = c - 3.0 * bb / 8.0;
+                let q = d - b * c / 2.0 + bb * b / 8.0;
+                let r = e - b * d / 4.0 + bb * c / 16.0 - 3.0 * bb * bb / 256.0;
+                let shift = -b / 4.0;
+                let mut roots = Vec::with_capacity(4);
+                if q.abs() <= <$t>::EPSILON * (1.0 + p.abs() + r.abs()) {
+                    // biquadratic
+                    for z in Polynomial::<$t>::quadratic(1.0,p,r) {
+                        let y = z.sqrt();
+                        roots.push(y + shift);
+                        roots.push(-y + shift);
+                    }
+                    return roots;
+                }
+
+                // (y^2 + p / 2 + m)^2 = (s y - q / (2 s))^2 with s^2 = 2m, where m solves the resolvent cubic
+                let m = Polynomial::<$t>::cubic(8.0,8.0 * p,2.0 * p * p - 8.0 * r,-q * q).into_iter().fold(Complex::<$t>::zero(),|best,m| if m.abs() > best.abs() {
+                    m
+                }
+                else {
+                    best
+                });
+                let s = (2.0 * m).sqrt();
+                for sign in [1.0,-1.0].iter() {
+                    let b1 = -*sign * s;
+                    let c1 = 0.5 * p + m + *sign * q / (2.0 * s);
+                    let t = (b1 * b1 - 4.0 * c1).sqrt();
+                    roots.push((t - b1) * 0.5 + shift);
+                    roots.push((-t - b1) * 0.5 + shift);
+                }
+                roots
+            }
+        }
+
+        impl cmp::PartialEq for Polynomial<$t> {
+            fn eq(&self,other: &Polynomial<$t>) -> bool {
+                self.c == other.c
+            }
+        }
+
+        impl fmt::Display for Polynomial<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                if self.c.is_empty() {
+                    return write!(f,"0");
+                }
+                let mut first = true;
+                for (n,c) in self.c.iter().enumerate() {
+                    if *c == 0.0 {
+                        continue;
+                    }
+                    let sc = if !first && (*c >= 0.0) {
+                        format!("+{}",c)
+                    } else {
+                        format!("{}",c)
+                    };
+                    match n {
+                        0 => write!(f,"{}",sc)?,
+                        1 => write!(f,"{}x",sc)?,
+                        _ => write!(f,"{}x^{}",sc,n)?,
+                    }
+                    first = false;
+                }
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for Polynomial<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(self,f)
+            }
+        }
+
+        impl Zero for Polynomial<$t> {
+            fn zero() -> Polynomial<$t> {
+                Polynomial {
+                    c: Vec::new(),
+                }
+            }
+        }
+
+        impl One for Polynomial<$t> {
+            fn one() -> Polynomial<$t> {
+                Polynomial {
+                    c: vec![1.0],
+                }
+            }
+        }
+
+        impl ops::Neg for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn neg(self) -> Self::Output {
+                Polynomial {
+                    c: self.c.iter().map(|c| -c).collect(),
+                }
+            }
+        }
+
+        impl ops::Add<Polynomial<$t>> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn add(self,other: Polynomial<$t>) -> Self::Output {
+                let n = self.c.len().max(other.c.len());
+                Polynomial::<$t>::new((0..n).map(|k| self.c.get(k).unwrap_or(&0.0) + other.c.get(k).unwrap_or(&0.0)).collect())
+            }
+        }
+
+        impl ops::Sub<Polynomial<$t>> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn sub(self,other: Polynomial<$t>) -> Self::Output {
+                let n = self.c.len().max(other.c.len());
+                Polynomial::<$t>::new((0..n).map(|k| self.c.get(k).unwrap_or(&0.0) - other.c.get(k).unwrap_or(&0.0)).collect())
+            }
+        }
+
+        impl ops::AddAssign<Polynomial<$t>> for Polynomial<$t> {
+            fn add_assign(&mut self,other: Polynomial<$t>) {
+                if self.c.len() < other.c.len() {
+                    self.c.resize(other.c.len(),0.0);
+                }
+                for (a,b) in self.c.iter_mut().zip(other.c.iter()) {
+                    *a += b;
+                }
+                self.trim();
+            }
+        }
+
+        impl ops::SubAssign<Polynomial<$t>> for Polynomial<$t> {
+            fn sub_assign(&mut self,other: Polynomial<$t>) {
+                if self.c.len() < other.c.len() {
+                    self.c.resize(other.c.len(),0.0);
+                }
+                for (a,b) in self.c.iter_mut().zip(other.c.iter()) {
+                    *a -= b;
+                }
+                self.trim();
+            }
+        }
+
+        impl ops::Mul<$t> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn mul(self,other: $t) -> Self::Output {
+                Polynomial::<$t>::new(self.c.iter().map(|c| c * other).collect())
+            }
+        }
+
+        impl ops::Mul<Polynomial<$t>> for $t {
+            type Output = Polynomial<$t>;
+            fn mul(self,other: Polynomial<$t>) -> Self::Output {
+                Polynomial::<$t>::new(other.c.iter().map(|c| self * c).collect())
+            }
+        }
+
+        impl ops::Mul<Polynomial<$t>> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn mul(self,other: Polynomial<$t>) -> Self::Output {
+                if self.c.is_empty() || other.c.is_empty() {
+                    return Polynomial::<$t>::zero();
+                }
+                let mut c = vec![0.0; self.c.len() + other.c.len() - 1];
+                for (i,a) in self.c.iter().enumerate() {
+                    for (j,b) in other.c.iter().enumerate() {
+                        c[i + j] += a * b;
+                    }
+                }
+                Polynomial::<$t>::new(c)
+            }
+        }
+
+        impl ops::MulAssign<$t> for Polynomial<$t> {
+            fn mul_assign(&mut self,other: $t) {
+                for c in self.c.iter_mut() {
+                    *c *= other;
+                }
+                self.trim();
+            }
+        }
+
+        impl ops::Div<$t> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn div(self,other: $t) -> Self::Output {
+                if other != 0.0 {
+                    Polynomial {
+                        c: self.c.iter().map(|c| c / other).collect(),
+                    }
+                }
+                else {
+                    self
+                }
+            }
+        }
+
+        impl ops::Div<Polynomial<$t>> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn div(self,other: Polynomial<$t>) -> Self::Output {
+                self.div_rem(&other).0
+            }
+        }
+
+        impl ops::Rem<Polynomial<$t>> for Polynomial<$t> {
+            type Output = Polynomial<$t>;
+            fn rem(self,other: Polynomial<$t>) -> Self::Output {
+                self.div_rem(&other).1
+            }
+        }
+
+        impl ops::DivAssign<$t> for Polynomial<$t> {
+            fn div_assign(&mut self,other: $t) {
+                if other != 0.0 {
+                    for c in self.c.iter_mut() {
+                        *c /= other;
+                    }
+                }
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type f32poly = Polynomial<f32>;
+
+impl_polynomial!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64poly = Polynomial<f64>;
+
+impl_polynomial!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_zeros_are_trimmed() {
+        let p = f64poly::new(vec![2.0,-3.0,1.0,0.0,0.0]);
+        assert_eq!(p.coefficients(),&[2.0,-3.0,1.0]);
+        assert_eq!(p.degree(),2);
+        assert!(f64poly::new(vec![0.0,0.0]).coefficients().is_empty());
+    }
+
+    #[test]
+    fn simple_roots() {
+        // (x - 1)(x - 2)(x - 3)(x + 4)
+        let p = f64poly::new(vec![-24.0,38.0,-13.0,-2.0,1.0,0.0]);
+        let mut roots: Vec<f64> = p.roots().iter().map(|z| {
+            assert!(z.i.abs() < 1e-9);
+            z.r
+        }).collect();
+        roots.sort_by(|a,b| a.total_cmp(b));
+        for (r,e) in roots.iter().zip([-4.0,1.0,2.0,3.0].iter()) {
+            assert!((r - e).abs() < 1e-9);
+        }
+    }
+
+    // a root of multiplicity m is only found to about EPSILON^(1/m)
+    #[test]
+    fn multiple_root_tolerance() {
+        let p = f64poly::new(vec![1.0,-4.0,6.0,-4.0,1.0]);
+        for z in p.roots() {
+            assert!((z - f64c::new(1.0,0.0)).abs() < 4.0 * f64::EPSILON.powf(0.25));
+        }
+        let p = f32poly::new(vec![1.0,-4.0,6.0,-4.0,1.0]);
+        for z in p.roots() {
+            assert!((z - f32c::new(1.0,0.0)).abs() < 4.0 * f32::EPSILON.powf(0.25));
+        }
+    }
+
+    // every expected root matches a different found root within tol
+    fn same_roots(found: &[f64c],expected: &[(f64,f64)],tol: f64) -> bool {
+        let mut left: Vec<f64c> = found.to_vec();
+        found.len() == expected.len() && expected.iter().all(|&(r,i)| {
+            let e = f64c::new(r,i);
+            match left.iter().position(|z| (*z - e).abs() < tol) {
+                Some(n) => {
+                    left.swap_remove(n);
+                    true
+                },
+                None => false,
+            }
+        })
+    }
+
+    #[test]
+    fn cubic() {
+        let s3 = 3.0f64.sqrt();
+        // (x - 1)(x - 2)(x - 3)
+        assert!(same_roots(&f64poly::cubic(1.0,-6.0,11.0,-6.0),&[(1.0,0.0),(2.0,0.0),(3.0,0.0)],1e-12));
+        assert!(same_roots(&f64poly::cubic(2.0,-12.0,22.0,-12.0),&[(1.0,0.0),(2.0,0.0),(3.0,0.0)],1e-12));
+        // (x - 2)^3
+        assert!(same_roots(&f64poly::cubic(1.0,-6.0,12.0,-8.0),&[(2.0,0.0),(2.0,0.0),(2.0,0.0)],1e-12));
+        // x^3 - 8
+        assert!(same_roots(&f64poly::cubic(1.0,0.0,0.0,-8.0),&[(2.0,0.0),(-1.0,s3),(-1.0,-s3)],1e-12));
+        // a = 0 falls back to the quadratic x^2 - 3x + 2
+        assert!(same_roots(&f64poly::cubic(0.0,1.0,-3.0,2.0),&[(1.0,0.0),(2.0,0.0)],1e-12));
+    }
+
+    #[test]
+    fn quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert!(same_roots(&f64poly::quartic(1.0,-10.0,35.0,-50.0,24.0),&[(1.0,0.0),(2.0,0.0),(3.0,0.0),(4.0,0.0)],1e-9));
+        // x^4 - 1 takes the biquadratic path
+        assert!(same_roots(&f64poly::quartic(1.0,0.0,0.0,0.0,-1.0),&[(1.0,0.0),(-1.0,0.0),(0.0,1.0),(0.0,-1.0)],1e-12));
+        // (x - 1)^4
+        assert!(same_roots(&f64poly::quartic(1.0,-4.0,6.0,-4.0,1.0),&[(1.0,0.0),(1.0,0.0),(1.0,0.0),(1.0,0.0)],1e-12));
+        // (x^2 + 1)(x - 2)(x + 3), complex and real roots together
+        assert!(same_roots(&f64poly::quartic(1.0,1.0,-5.0,1.0,-6.0),&[(0.0,1.0),(0.0,-1.0),(2.0,0.0),(-3.0,0.0)],1e-9));
+        // a = 0 falls back to the cubic
+        assert!(same_roots(&f64poly::quartic(0.0,1.0,-6.0,11.0,-6.0),&[(1.0,0.0),(2.0,0.0),(3.0,0.0)],1e-12));
+    }
+}
@@ -2,10 +2,10 @@
 // Math
 // Quaternions
 
-use std::{cmp,fmt};
+use std::{cmp,fmt,ops};
 use crate::*;
 
-#[derive(Clone)]
+#[derive(Copy,Clone)]
 pub struct Quaternion<T> {
     pub r: T,
     pub i: T,
@@ -13,81 +13,762 @@ pub struct Quaternion<T> {
     pub k: T,
 }
 
+macro_rules! impl_quaternion (
+    ($t:ty) => (
+        impl Quaternion<$t> {
+            pub fn new(r: $t,i: $t,j: $t,k: $t) -> Quaternion<$t> {
+                Quaternion {
+                    r: r,
+                    i: i,
+                    j: j,
+                    k: k,
+                }
+            }
+
+            pub fn from_axis_angle(axis: XYZ<$t>,angle: $t) -> Quaternion<$t> {
+                let a = axis.norm();
+                let sa = (0.5 * angle).sin();
+                let ca = (0.5 * angle).cos();
+                Quaternion {
+                    r: ca,
+                    i: sa * a.x,
+                    j: sa * a.y,
+                    k: sa * a.z,
+                }
+            }
+
+            // shortest rotation that turns direction from into direction to
+            pub fn from_rotation_arc(from: XYZ<$t>,to: XYZ<$t>) -> Quaternion<$t> {
+                let a = from.norm();
+                let b = to.norm();
+                let r = 1.0 + XYZ::<$t>::dot(a,b);
+                if r < 1e-6 {
+                    // opposite directions: rotate half a turn around any perpendicular axis
+                    let mut axis = XYZ::<$t>::cross(XYZ::<$t>::new(1.0,0.0,0.0),a);
+                    if XYZ::<$t>::dot(axis,axis) < 1e-6 {
+                        axis = XYZ::<$t>::cross(XYZ::<$t>::new(0.0,1.0,0.0),a);
+                    }
+                    let axis = axis.norm();
+                    Quaternion {
+                        r: 0.0,
+                        i: axis.x,
+                        j: axis.y,
+                        k: axis.z,
+                    }
+                }
+                else {
+                    let c = XYZ::<$t>::cross(a,b);
+                    Quaternion {
+                        r: r,
+                        i: c.x,
+                        j: c.y,
+                        k: c.z,
+                    }.norm()
+                }
+            }
+
+            // rotation that turns +Z into forward and +Y as close as possible to up
+            pub fn look_rotation(forward: XYZ<$t>,up: XYZ<$t>) -> Quaternion<$t> {
+                let z = forward.norm();
+                let x = XYZ::<$t>::cross(up,z);
+                if XYZ::<$t>::dot(x,x) < 1e-12 {
+                    return Quaternion::<$t>::from_rotation_arc(XYZ::<$t>::new(0.0,0.0,1.0),z);
+                }
+                let x = x.norm();
+                let y = XYZ::<$t>::cross(z,x);
+                Quaternion::<$t>::from(Mat3x3::<$t>::new(x,y,z))
+            }
+
+            // q v q*, assuming q is a unit quaternion
+            pub fn rotate(&self,v: XYZ<$t>) -> XYZ<$t> {
+                let u = XYZ::<$t>::new(self.i,self.j,self.k);
+                let t = 2.0 * XYZ::<$t>::cross(u,v);
+                v + self.r * t + XYZ::<$t>::cross(u,t)
+            }
+
+            pub fn dot(a: Quaternion<$t>,b: Quaternion<$t>) -> $t {
+                a.r * b.r + a.i * b.i + a.j * b.j + a.k * b.k
+            }
+
+            pub fn conj(self) -> Quaternion<$t> {
+                Quaternion {
+                    r: self.r,
+                    i: -self.i,
+                    j: -self.j,
+                    k: -self.k,
+                }
+            }
+
+            pub fn abs(self) -> $t {
+                (self.r * self.r + self.i * self.i + self.j * self.j + self.k * self.k).sqrt()
+            }
+
+            pub fn norm(self) -> Quaternion<$t> {
+                let d = self.abs();
+                if d != 0.0 {
+                    self / d
+                }
+                else {
+                    self
+                }
+            }
+
+            pub fn inverse(self) -> Quaternion<$t> {
+                let d = self.r * self.r + self.i * self.i + self.j * self.j + self.k * self.k;
+                if d != 0.0 {
+                    self.conj() / d
+                }
+                else {
+                    Quaternion::one()
+                }
+            }
+
+            pub fn exp(self) -> Quaternion<$t> {
+                let a = (self.i * self.i + self.j * self.j + self.k * self.k).sqrt();
+                let e = self.r.exp();
+                let s = if a != 0.0 {
+                    e * a.sin() / a
+                }
+                else {
+                    e
+                };
+                Quaternion {
+                    r: e * a.cos(),
+                    i: s * self.i,
+                    j: s * self.j,
+                    k: s * self.k,
+                }
+            }
+
+            pub fn ln(self) -> Quaternion<$t> {
+                let a = (self.i * self.i + self.j * self.j + self.k * self.k).sqrt();
+                let n = (self.r * self.r + a * a).sqrt();
+                let s = if a != 0.0 {
+                    a.atan2(self.r) / a
+                }
+                else {
+                    0.0
+                };
+                Quaternion {
+                    r: n.ln(),
+                    i: s * self.i,
+                    j: s * self.j,
+                    k: s * self.k,
+                }
+            }
+
+            pub fn powf(self,t: $t) -> Quaternion<$t> {
+                (self.ln() * t).exp()
+            }
+
+            // advance orientation by world-space angular velocity omega over time dt
+            pub fn integrate(self,omega: XYZ<$t>,dt: $t) -> Quaternion<$t> {
+                let h = 0.5 * dt;
+                (Quaternion {
+                    r: 0.0,
+                    i: h * omega.x,
+                    j: h * omega.y,
+                    k: h * omega.z,
+                }.exp() * self).norm()
+            }
+
+            pub fn nlerp(a: Quaternion<$t>,b: Quaternion<$t>,t: $t) -> Quaternion<$t> {
+                let b = if Quaternion::<$t>::dot(a,b) < 0.0 {
+                    -b
+                }
+                else {
+                    b
+                };
+                (a + (b - a) * t).norm()
+            }
+
+            pub fn slerp(a: Quaternion<$t>,b: Quaternion<$t>,t: $t) -> Quaternion<$t> {
+                let mut d = Quaternion::<$t>::dot(a,b);
+                let b = if d < 0.0 {
+                    d = -d;
+                    -b
+                }
+                else {
+                    b
+                };
+                if d > 0.9995 {
+                    // nearly parallel, sin(theta) too small to divide by
+                    (a + (b - a) * t).norm()
+                }
+                else {
+                    let theta = d.acos();
+                    let st = theta.sin();
+                    (a * ((1.0 - t) * theta).sin() + b * (t * theta).sin()) / st
+                }
+            }
+
+            // slerp without the sign flip, squad needs the path it is given
+            fn slerp_no_flip(a: Quaternion<$t>,b: Quaternion<$t>,t: $t) -> Quaternion<$t> {
+                let d = Quaternion::<$t>::dot(a,b).max(-1.0).min(1.0);
+                if d.abs() > 0.9995 {
+                    (a + (b - a) * t).norm()
+                }
+                else {
+                    let theta = d.acos();
+                    let st = theta.sin();
+                    (a * ((1.0 - t) * theta).sin() + b * (t * theta).sin()) / st
+                }
+            }
+
+            // inner control point for key q between keys prev and next
+            pub fn squad_tangent(prev: Quaternion<$t>,q: Quaternion<$t>,next: Quaternion<$t>) -> Quaternion<$t> {
+                let qi = q.conj();
+                let l = ((qi * prev).ln() + (qi * next).ln()) * -0.25;
+                q * l.exp()
+            }
+
+            pub fn squad(q0: Quaternion<$t>,q1: Quaternion<$t>,s0: Quaternion<$t>,s1: Quaternion<$t>,t: $t) -> Quaternion<$t> {
+                Quaternion::<$t>::slerp_no_flip(
+                    Quaternion::<$t>::slerp_no_flip(q0,q1,t),
+                    Quaternion::<$t>::slerp_no_flip(s0,s1,t),
+                    2.0 * t * (1.0 - t)
+                )
+            }
+
+            // smooth interpolation through keys, t runs from 0 to keys.len() - 1
+            pub fn squad_spline(keys: &[Quaternion<$t>],t: $t) -> Quaternion<$t> {
+                let n = keys.len();
+                if n == 0 {
+                    return Quaternion::one();
+                }
+                if n == 1 {
+                    return keys[0];
+                }
+                let t = t.max(0.0).min((n - 1) as $t);
+                let i = (t.floor() as usize).min(n - 2);
+                let f = t - i as $t;
+                let align = |q: Quaternion<$t>,to: Quaternion<$t>| if Quaternion::<$t>::dot(q,to) < 0.0 {
+                    -q
+                }
+                else {
+                    q
+                };
+                let q0 = keys[i];
+                let q1 = align(keys[i + 1],q0);
+                let qp = align(keys[if i > 0 { i - 1 } else { 0 }],q0);
+                let qn = align(keys[(i + 2).min(n - 1)],q1);
+                let s0 = Quaternion::<$t>::squad_tangent(qp,q0,q1);
+                let s1 = Quaternion::<$t>::squad_tangent(q0,q1,qn);
+                Quaternion::<$t>::squad(q0,q1,s0,s1,f)
+            }
+        }
+
+        impl From<Quaternion<$t>> for Mat3x3<$t> {
+            fn from(q: Quaternion<$t>) -> Mat3x3<$t> {
+                let n = q.r * q.r + q.i * q.i + q.j * q.j + q.k * q.k;
+                let s = if n != 0.0 {
+                    2.0 / n
+                }
+                else {
+                    0.0
+                };
+                let ii = s * q.i * q.i;
+                let jj = s * q.j * q.j;
+                let kk = s * q.k * q.k;
+                let ij = s * q.i * q.j;
+                let ik = s * q.i * q.k;
+                let jk = s * q.j * q.k;
+                let ri = s * q.r * q.i;
+                let rj = s * q.r * q.j;
+                let rk = s * q.r * q.k;
+                Mat3x3 {
+                    x: XYZ::<$t>::new(1.0 - (jj + kk),ij + rk,ik - rj),
+                    y: XYZ::<$t>::new(ij - rk,1.0 - (ii + kk),jk + ri),
+                    z: XYZ::<$t>::new(ik + rj,jk - ri,1.0 - (ii + jj)),
+                }
+            }
+        }
+
+        impl From<Quaternion<$t>> for Mat4x4<$t> {
+            fn from(q: Quaternion<$t>) -> Mat4x4<$t> {
+                let m = Mat3x3::<$t>::from(q);
+                Mat4x4 {
+                    x: XYZW::<$t>::new(m.x.x,m.x.y,m.x.z,0.0),
+                    y: XYZW::<$t>::new(m.y.x,m.y.y,m.y.z,0.0),
+                    z: XYZW::<$t>::new(m.z.x,m.z.y,m.z.z,0.0),
+                    w: XYZW::<$t>::new(0.0,0.0,0.0,1.0),
+                }
+            }
+        }
+
+        // Shepperd's method: pivot on the largest of the trace and the diagonal to avoid cancellation
+        impl From<Mat3x3<$t>> for Quaternion<$t> {
+            fn from(m: Mat3x3<$t>) -> Quaternion<$t> {
+                let trace = m.x.x + m.y.y + m.z.z;
+                if (trace >= m.x.x) && (trace >= m.y.y) && (trace >= m.z.z) {
+                    let s = 2.0 * (1.0 + trace).sqrt();
+                    Quaternion {
+                        r: 0.25 * s,
+                        i: (m.y.z - m.z.y) / s,
+                        j: (m.z.x - m.x.z) / s,
+                        k: (m.x.y - m.y.x) / s,
+                    }
+                }
+                else if (m.x.x >= m.y.y) && (m.x.x >= m.z.z) {
+                    let s = 2.0 * (1.0 + m.x.x - m.y.y - m.z.z).sqrt();
+                    Quaternion {
+                        r: (m.y.z - m.z.y) / s,
+                        i: 0.25 * s,
+                        j: (m.y.x + m.x.y) / s,
+                        k: (m.z.x + m.x.z) / s,
+                    }
+                }
+                else if m.y.y >= m.z.z {
+                    let s = 2.0 * (1.0 + m.y.y - m.x.x - m.z.z).sqrt();
+                    Quaternion {
+                        r: (m.z.x - m.x.z) / s,
+                        i: (m.y.x + m.x.y) / s,
+                        j: 0.25 * s,
+                        k: (m.z.y + m.y.z) / s,
+                    }
+                }
+                else {
+                    let s = 2.0 * (1.0 + m.z.z - m.x.x - m.y.y).sqrt();
+                    Quaternion {
+                        r: (m.x.y - m.y.x) / s,
+                        i: (m.z.x + m.x.z) / s,
+                        j: (m.z.y + m.y.z) / s,
+                        k: 0.25 * s,
+                    }
+                }
+            }
+        }
+
+        impl From<Mat4x4<$t>> for Quaternion<$t> {
+            fn from(m: Mat4x4<$t>) -> Quaternion<$t> {
+                Quaternion::<$t>::from(Mat3x3 {
+                    x: XYZ::<$t>::new(m.x.x,m.x.y,m.x.z),
+                    y: XYZ::<$t>::new(m.y.x,m.y.y,m.y.z),
+                    z: XYZ::<$t>::new(m.z.x,m.z.y,m.z.z),
+                })
+            }
+        }
+
+        impl cmp::PartialEq for Quaternion<$t> {
+            fn eq(&self,other: &Quaternion<$t>) -> bool {
+                (self.r == other.r)
+                && (self.i == other.i)
+                && (self.j == other.j)
+                && (self.k == other.k)
+            }
+        }
+
+        impl fmt::Display for Quaternion<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                let si = if self.i < 0.0 {
+                    format!("{}i",self.i)
+                } else {
+                    format!("+{}i",self.i)
+                };
+                let sj = if self.j < 0.0 {
+                    format!("{}j",self.j)
+                } else {
+                    format!("+{}j",self.j)
+                };
+                let sk = if self.k < 0.0 {
+                    format!("{}k",self.k)
+                } else {
+                    format!("+{}k",self.k)
+                };
+                write!(f,"{}{}{}{}",self.r,si,sj,sk)
+            }
+        }
+
+        impl fmt::Debug for Quaternion<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                let si = if self.i < 0.0 {
+                    format!("{}i",self.i)
+                } else {
+                    format!("+{}i",self.i)
+                };
+                let sj = if self.j < 0.0 {
+                    format!("{}j",self.j)
+                } else {
+                    format!("+{}j",self.j)
+                };
+                let sk = if self.k < 0.0 {
+                    format!("{}k",self.k)
+                } else {
+                    format!("+{}k",self.k)
+                };
+                write!(f,"{}{}{}{}",self.r,si,sj,sk)
+            }
+        }
+
+        impl Zero for Quaternion<$t> {
+            fn zero() -> Quaternion<$t> {
+                Quaternion {
+                    r: 0.0,
+                    i: 0.0,
+                    j: 0.0,
+                    k: 0.0,
+                }
+            }
+        }
+
+        impl One for Quaternion<$t> {
+            fn one() -> Quaternion<$t> {
+                Quaternion {
+                    r: 1.0,
+                    i: 0.0,
+                    j: 0.0,
+                    k: 0.0,
+                }
+            }
+        }
+
+        impl ops::Neg for Quaternion<$t> {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Quaternion {
+                    r: -self.r,
+                    i: -self.i,
+                    j: -self.j,
+                    k: -self.k,
+                }
+            }
+        }
+
+        impl ops::Add<$t> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn add(self,other: $t) -> Self::Output {
+                Quaternion {
+                    r: self.r + other,
+                    i: self.i,
+                    j: self.j,
+                    k: self.k,
+                }
+            }
+        }
+
+        impl ops::Add<Quaternion<$t>> for $t {
+            type Output = Quaternion<$t>;
+            fn add(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self + other.r,
+                    i: other.i,
+                    j: other.j,
+                    k: other.k,
+                }
+            }
+        }
+
+        impl ops::Add<Quaternion<$t>> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn add(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self.r + other.r,
+                    i: self.i + other.i,
+                    j: self.j + other.j,
+                    k: self.k + other.k,
+                }
+            }
+        }
+
+        impl ops::AddAssign<$t> for Quaternion<$t> {
+            fn add_assign(&mut self,other: $t) {
+                self.r += other;
+            }
+        }
+
+        impl ops::AddAssign<Quaternion<$t>> for Quaternion<$t> {
+            fn add_assign(&mut self,other: Quaternion<$t>) {
+                self.r += other.r;
+                self.i += other.i;
+                self.j += other.j;
+                self.k += other.k;
+            }
+        }
+
+        impl ops::Sub<$t> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn sub(self,other: $t) -> Self::Output {
+                Quaternion {
+                    r: self.r - other,
+                    i: self.i,
+                    j: self.j,
+                    k: self.k,
+                }
+            }
+        }
+
+        impl ops::Sub<Quaternion<$t>> for $t {
+            type Output = Quaternion<$t>;
+            fn sub(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self - other.r,
+                    i: -other.i,
+                    j: -other.j,
+                    k: -other.k,
+                }
+            }
+        }
+
+        impl ops::Sub<Quaternion<$t>> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn sub(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self.r - other.r,
+                    i: self.i - other.i,
+                    j: self.j - other.j,
+                    k: self.k - other.k,
+                }
+            }
+        }
+
+        impl ops::SubAssign<$t> for Quaternion<$t> {
+            fn sub_assign(&mut self,other: $t) {
+                self.r -= other;
+            }
+        }
+
+        impl ops::SubAssign<Quaternion<$t>> for Quaternion<$t> {
+            fn sub_assign(&mut self,other: Quaternion<$t>) {
+                self.r -= other.r;
+                self.i -= other.i;
+                self.j -= other.j;
+                self.k -= other.k;
+            }
+        }
+
+        impl ops::Mul<$t> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn mul(self,other: $t) -> Self::Output {
+                Quaternion {
+                    r: self.r * other,
+                    i: self.i * other,
+                    j: self.j * other,
+                    k: self.k * other,
+                }
+            }
+        }
+
+        impl ops::Mul<Quaternion<$t>> for $t {
+            type Output = Quaternion<$t>;
+            fn mul(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self * other.r,
+                    i: self * other.i,
+                    j: self * other.j,
+                    k: self * other.k,
+                }
+            }
+        }
+
+        impl ops::Mul<Quaternion<$t>> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn mul(self,other: Quaternion<$t>) -> Self::Output {
+                Quaternion {
+                    r: self.r * other.r - self.i * other.i - self.j * other.j - self.k * other.k,
+                    i: self.r * other.i + self.i * other.r + self.j * other.k - self.k * other.j,
+                    j: self.r * other.j - self.i * other.k + self.j * other.r + self.k * other.i,
+                    k: self.r * other.k + self.i * other.j - self.j * other.i + self.k * other.r,
+                }
+            }
+        }
+
+        impl ops::MulAssign<$t> for Quaternion<$t> {
+            fn mul_assign(&mut self,other: $t) {
+                self.r *= other;
+                self.i *= other;
+                self.j *= other;
+                self.k *= other;
+            }
+        }
+
+        impl ops::MulAssign<Quaternion<$t>> for Quaternion<$t> {
+            fn mul_assign(&mut self,other: Quaternion<$t>) {
+                let nr = self.r * other.r - self.i * other.i - self.j * other.j - self.k * other.k;
+                let ni = self.r * other.i + self.i * other.r + self.j * other.k - self.k * other.j;
+                let nj = self.r * other.j - self.i * other.k + self.j * other.r + self.k * other.i;
+                let nk = self.r * other.k + self.i * other.j - self.j * other.i + self.k * other.r;
+                self.r = nr;
+                self.i = ni;
+                self.j = nj;
+                self.k = nk;
+            }
+        }
+
+        impl ops::Div<$t> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn div(self,other: $t) -> Self::Output {
+                if other != 0.0 {
+                    Quaternion {
+                        r: self.r / other,
+                        i: self.i / other,
+                        j: self.j / other,
+                        k: self.k / other,
+                    }
+                }
+                else {
+                    self
+                }
+            }
+        }
+
+        impl ops::Div<Quaternion<$t>> for $t {
+            type Output = Quaternion<$t>;
+            fn div(self,other: Quaternion<$t>) -> Self::Output {
+                let d = other.r * other.r + other.i * other.i + other.j * other.j + other.k * other.k;
+                if d != 0.0 {
+                    Quaternion {
+                        r: self * other.r / d,
+                        i: -self * other.i / d,
+                        j: -self * other.j / d,
+                        k: -self * other.k / d,
+                    }
+                }
+                else {
+                    Quaternion {
+                        r: self,
+                        i: 0.0,
+                        j: 0.0,
+                        k: 0.0,
+                    }
+                }
+            }
+        }
+
+        // right division: a / b = a * b^-1
+        impl ops::Div<Quaternion<$t>> for Quaternion<$t> {
+            type Output = Quaternion<$t>;
+            fn div(self,other: Quaternion<$t>) -> Self::Output {
+                let d = other.r * other.r + other.i * other.i + other.j * other.j + other.k * other.k;
+                if d != 0.0 {
+                    Quaternion {
+                        r: (self.r * other.r + self.i * other.i + self.j * other.j + self.k * other.k) / d,
+                        i: (self.i * other.r - self.r * other.i - self.j * other.k + self.k * other.j) / d,
+                        j: (self.j * other.r - self.r * other.j + self.i * other.k - self.k * other.i) / d,
+                        k: (self.k * other.r - self.r * other.k - self.i * other.j + self.j * other.i) / d,
+                    }
+                }
+                else {
+                    self
+                }
+            }
+        }
+
+        impl ops::DivAssign<$t> for Quaternion<$t> {
+            fn div_assign(&mut self,other: $t) {
+                if other != 0.0 {
+                    self.r /= other;
+                    self.i /= other;
+                    self.j /= other;
+                    self.k /= other;
+                }
+            }
+        }
+
+        impl ops::DivAssign<Quaternion<$t>> for Quaternion<$t> {
+            fn div_assign(&mut self,other: Quaternion<$t>) {
+                let d = other.r * other.r + other.i * other.i + other.j * other.j + other.k * other.k;
+                if d != 0.0 {
+                    let nr = (self.r * other.r + self.i * other.i + self.j * other.j + self.k * other.k) / d;
+                    let ni = (self.i * other.r - self.r * other.i - self.j * other.k + self.k * other.j) / d;
+                    let nj = (self.j * other.r - self.r * other.j + self.i * other.k - self.k * other.i) / d;
+                    let nk = (self.k * other.r - self.r * other.k - self.i * other.j + self.j * other.i) / d;
+                    self.r = nr;
+                    self.i = ni;
+                    self.j = nj;
+                    self.k = nk;
+                }
+            }
+        }
+    );
+);
+
 #[allow(non_camel_case_types)]
 pub type f32q = Quaternion<f32>;
 
+impl_quaternion!(f32);
+
 #[allow(non_camel_case_types)]
 pub type f64q = Quaternion<f64>;
 
-impl<T> Quaternion<T> {
-    pub fn new(r: T,i: T,j: T,k: T) -> Quaternion<T> {
-        Quaternion {
-            r: r,
-            i: i,
-            j: j,
-            k: k,
-        }
+impl_quaternion!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units() -> (f64q,f64q,f64q,f64q) {
+        (
+            f64q::new(1.0,0.0,0.0,0.0),
+            f64q::new(0.0,1.0,0.0,0.0),
+            f64q::new(0.0,0.0,1.0,0.0),
+            f64q::new(0.0,0.0,0.0,1.0),
+        )
     }
-}
 
-impl<T: cmp::PartialEq> cmp::PartialEq for Quaternion<T> {
-    fn eq(&self,other: &Quaternion<T>) -> bool {
-        (self.r == other.r)
-        && (self.i == other.i)
-        && (self.j == other.j)
-        && (self.k == other.k)
+    #[test]
+    fn hamilton_identities() {
+        let (one,i,j,k) = units();
+        assert_eq!(i * i,-one);
+        assert_eq!(j * j,-one);
+        assert_eq!(k * k,-one);
+        assert_eq!(i * j * k,-one);
     }
-}
 
-impl<T: fmt::Display + cmp::PartialOrd + Zero> fmt::Display for Quaternion<T> {
-    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
-        let si = if self.i < T::zero() {
-            format!("{}i",self.i)
-        } else {
-            format!("+{}i",self.i)
-        };
-        let sj = if self.j < T::zero() {
-            format!("{}j",self.j)
-        } else {
-            format!("+{}j",self.j)
-        };
-        let sk = if self.k < T::zero() {
-            format!("{}k",self.k)
-        } else {
-            format!("+{}k",self.k)
-        };
-        write!(f,"{}{}{}{}",self.r,si,sj,sk)
+    #[test]
+    fn products_of_units() {
+        let (_,i,j,k) = units();
+        assert_eq!(i * j,k);
+        assert_eq!(j * k,i);
+        assert_eq!(k * i,j);
+        assert_eq!(j * i,-k);
+        assert_eq!(k * j,-i);
+        assert_eq!(i * k,-j);
     }
-}
 
-impl<T: fmt::Display + cmp::PartialOrd + Zero> fmt::Debug for Quaternion<T> {
-    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
-        let si = if self.i < T::zero() {
-            format!("{}i",self.i)
-        } else {
-            format!("+{}i",self.i)
-        };
-        let sj = if self.j < T::zero() {
-            format!("{}j",self.j)
-        } else {
-            format!("+{}j",self.j)
-        };
-        let sk = if self.k < T::zero() {
-            format!("{}k",self.k)
-        } else {
-            format!("+{}k",self.k)
-        };
-        write!(f,"{}{}{}{}",self.r,si,sj,sk)
+    #[test]
+    fn inverse() {
+        let q = f64q::new(1.0,2.0,-3.0,0.5);
+        let p = q * q.inverse();
+        assert!((p - f64q::one()).abs() < 1e-12);
+        assert!(((q / q) - f64q::one()).abs() < 1e-12);
+        assert_eq!(q.conj(),f64q::new(1.0,-2.0,3.0,-0.5));
     }
-}
 
-impl<T: Zero> Zero for Quaternion<T> {
-    fn zero() -> Quaternion<T> {
-        Quaternion {
-            r: T::zero(),
-            i: T::zero(),
-            j: T::zero(),
-            k: T::zero(),
+    #[test]
+    fn matrix_rotate_agrees_with_from() {
+        let q = f64q::new(0.3,-1.2,0.7,2.1);
+        let a = Mat3x3::<f64>::from(q);
+        let b = Mat3x3::<f64>::rotate(q.r,q.i,q.j,q.k);
+        let c = Mat4x4::<f64>::from(q);
+        let d = Mat4x4::<f64>::rotate(q.r,q.i,q.j,q.k);
+        let v = XYZ::<f64>::new(1.0,-2.0,0.5);
+        let close = |m: XYZ<f64>,n: XYZ<f64>| XYZ::<f64>::dot(m - n,m - n) < 1e-24;
+        assert!(close(a * v,b * v));
+        assert!(close(a * v,q.norm().rotate(v)));
+        assert!(close(a.x,b.x) && close(a.y,b.y) && close(a.z,b.z));
+        for (m,n) in [(c.x,d.x),(c.y,d.y),(c.z,d.z),(c.w,d.w)].iter() {
+            assert!(XYZW::<f64>::dot(*m - *n,*m - *n) < 1e-24);
+        }
+    }
+
+    #[test]
+    fn squad_spline_is_continuous() {
+        // with a sign-flipping slerp inside squad these keys jump by about 180 degrees
+        let keys = [
+            f64q::new(-0.27763053395361287,0.5545092058414851,-0.2559480915919192,-0.7415735982787762),
+            f64q::new(-0.22849857888428823,-0.25458301614950124,-0.9396331175451487,-0.008092697230740303),
+            f64q::new(0.07294994260797916,-0.22443996016292378,0.9445748290862293,-0.2282178836381554),
+            f64q::new(-0.04821784713285999,0.5833886883411951,0.7719257841528022,0.2479178519056127),
+        ];
+        let n = 3000;
+        let mut prev = f64q::squad_spline(&keys,0.0);
+        for s in 1..=n {
+            let q = f64q::squad_spline(&keys,3.0 * s as f64 / n as f64);
+            assert!(1.0 - f64q::dot(prev,q).abs() < 1e-4);
+            prev = q;
+        }
+        for (i,key) in keys.iter().enumerate() {
+            assert!(1.0 - f64q::dot(f64q::squad_spline(&keys,i as f64),*key).abs() < 1e-12);
         }
     }
 }
@@ -5,7 +5,7 @@
 use std::{cmp,fmt};
 use crate::*;
 
-#[derive(Clone)]
+#[derive(Copy,Clone)]
 pub struct Rectangle<T> {
     pub o: XY<T>,
     pub s: XY<T>,
@@ -39,6 +39,267 @@ impl<T: fmt::Display> fmt::Debug for Rectangle<T> where XY<T>: fmt::Display {
     }
 }
 
+// Rectangles are half-open: they contain o but not o + s.
+macro_rules! impl_rectangle (
+    ($t:ty) => (
+        impl Rectangle<$t> {
+            pub fn is_empty(&self) -> bool {
+                (self.s.x <= <$t>::zero())
+                || (self.s.y <= <$t>::zero())
+            }
+
+            pub fn area(&self) -> $t {
+                self.s.x * self.s.y
+            }
+
+            pub fn center(&self) -> XY<$t> {
+                XY {
+                    x: self.o.x + self.s.x / (2 as $t),
+                    y: self.o.y + self.s.y / (2 as $t),
+                }
+            }
+
+            pub fn translate(&self,d: XY<$t>) -> Rectangle<$t> {
+                Rectangle {
+                    o: self.o + d,
+                    s: self.s,
+                }
+            }
+
+            pub fn contains(&self,p: XY<$t>) -> bool {
+                (p.x >= self.o.x) && (p.x - self.o.x < self.s.x)
+                && (p.y >= self.o.y) && (p.y - self.o.y < self.s.y)
+            }
+
+            pub fn contains_rect(&self,other: &Rectangle<$t>) -> bool {
+                (other.o.x >= self.o.x) && (other.o.x - self.o.x + other.s.x <= self.s.x)
+                && (other.o.y >= self.o.y) && (other.o.y - self.o.y + other.s.y <= self.s.y)
+            }
+
+            pub fn intersect(&self,other: &Rectangle<$t>) -> Option<Rectangle<$t>> {
+                let x0 = self.o.x.max(other.o.x);
+                let y0 = self.o.y.max(other.o.y);
+                let x1 = (self.o.x + self.s.x).min(other.o.x + other.s.x);
+                let y1 = (self.o.y + self.s.y).min(other.o.y + other.s.y);
+                if (x1 > x0) && (y1 > y0) {
+                    Some(Rectangle {
+                        o: XY { x: x0,y: y0, },
+                        s: XY { x: x1 - x0,y: y1 - y0, },
+                    })
+                }
+                else {
+                    None
+                }
+            }
+
+            // smallest rectangle containing both, empty rectangles are ignored
+            pub fn union(&self,other: &Rectangle<$t>) -> Rectangle<$t> {
+                if other.is_empty() {
+                    return *self;
+                }
+                if self.is_empty() {
+                    return *other;
+                }
+                let x0 = self.o.x.min(other.o.x);
+                let y0 = self.o.y.min(other.o.y);
+                let x1 = (self.o.x + self.s.x).max(other.o.x + other.s.x);
+                let y1 = (self.o.y + self.s.y).max(other.o.y + other.s.y);
+                Rectangle {
+                    o: XY { x: x0,y: y0, },
+                    s: XY { x: x1 - x0,y: y1 - y0, },
+                }
+            }
+
+            // the part of self outside other, as at most 4 disjoint rectangles:
+            // full-width bands above and below other, and pieces left and right of it
+            pub fn subtract(&self,other: &Rectangle<$t>) -> Vec<Rectangle<$t>> {
+                if self.is_empty() {
+                    return Vec::new();
+                }
+                let i = match self.intersect(other) {
+                    Some(i) => i,
+                    None => return vec![*self],
+                };
+                let x1 = self.o.x + self.s.x;
+                let y1 = self.o.y + self.s.y;
+                let ix1 = i.o.x + i.s.x;
+                let iy1 = i.o.y + i.s.y;
+                let mut result = Vec::new();
+                if i.o.y > self.o.y {
+                    result.push(Rectangle {
+                        o: self.o,
+                        s: XY { x: self.s.x,y: i.o.y - self.o.y, },
+                    });
+                }
+                if i.o.x > self.o.x {
+                    result.push(Rectangle {
+                        o: XY { x: self.o.x,y: i.o.y, },
+                        s: XY { x: i.o.x - self.o.x,y: i.s.y, },
+                    });
+                }
+                if ix1 < x1 {
+                    result.push(Rectangle {
+                        o: XY { x: ix1,y: i.o.y, },
+                        s: XY { x: x1 - ix1,y: i.s.y, },
+                    });
+                }
+                if iy1 < y1 {
+                    result.push(Rectangle {
+                        o: XY { x: self.o.x,y: iy1, },
+                        s: XY { x: self.s.x,y: y1 - iy1, },
+                    });
+                }
+                result
+            }
+        }
+    );
+);
+
+// inflate and deflate saturate instead of wrapping, so unsigned rectangles
+// stop at 0 and deflated rectangles never get a negative size
+macro_rules! impl_rectangle_integer (
+    ($t:ty) => (
+        impl Rectangle<$t> {
+            pub fn inflate(&self,d: XY<$t>) -> Rectangle<$t> {
+                let x0 = self.o.x.saturating_sub(d.x);
+                let y0 = self.o.y.saturating_sub(d.y);
+                let x1 = self.o.x.saturating_add(self.s.x).saturating_add(d.x);
+                let y1 = self.o.y.saturating_add(self.s.y).saturating_add(d.y);
+                Rectangle {
+                    o: XY { x: x0,y: y0, },
+                    s: XY { x: x1 - x0,y: y1 - y0, },
+                }
+            }
+
+            pub fn deflate(&self,d: XY<$t>) -> Rectangle<$t> {
+                let c = self.center();
+                let x0 = self.o.x.saturating_add(d.x).min(c.x);
+                let y0 = self.o.y.saturating_add(d.y).min(c.y);
+                let x1 = (self.o.x + self.s.x).saturating_sub(d.x).max(x0);
+                let y1 = (self.o.y + self.s.y).saturating_sub(d.y).max(y0);
+                Rectangle {
+                    o: XY { x: x0,y: y0, },
+                    s: XY { x: x1 - x0,y: y1 - y0, },
+                }
+            }
+
+            // end corner, or o for empty rectangles so the ranges below are empty
+            fn end(&self) -> XY<$t> {
+                if self.is_empty() {
+                    self.o
+                }
+                else {
+                    self.o + self.s
+                }
+            }
+
+            // every point, in row-major order
+            pub fn points(&self) -> impl Iterator<Item = XY<$t>> {
+                let o = self.o;
+                let e = self.end();
+                (o.y..e.y).flat_map(move |y| (o.x..e.x).map(move |x| XY { x: x,y: y, }))
+            }
+
+            // tile_size pieces in row-major order, the last column and row are
+            // clipped to the rectangle, nothing if tile_size is not positive
+            pub fn tiles(&self,tile_size: XY<$t>) -> impl Iterator<Item = Rectangle<$t>> {
+                let o = self.o;
+                let e = if (tile_size.x > 0) && (tile_size.y > 0) {
+                    self.end()
+                }
+                else {
+                    self.o
+                };
+                let sx = tile_size.x.max(1) as usize;
+                let sy = tile_size.y.max(1) as usize;
+                (o.y..e.y).step_by(sy).flat_map(move |y| (o.x..e.x).step_by(sx).map(move |x| Rectangle {
+                    o: XY { x: x,y: y, },
+                    s: XY { x: tile_size.x.min(e.x - x),y: tile_size.y.min(e.y - y), },
+                }))
+            }
+
+            // one pixel high spans, top to bottom
+            pub fn rows(&self) -> impl Iterator<Item = Rectangle<$t>> {
+                let o = self.o;
+                let e = self.end();
+                (o.y..e.y).map(move |y| Rectangle {
+                    o: XY { x: o.x,y: y, },
+                    s: XY { x: e.x - o.x,y: 1, },
+                })
+            }
+
+            // one pixel wide spans, left to right
+            pub fn columns(&self) -> impl Iterator<Item = Rectangle<$t>> {
+                let o = self.o;
+                let e = self.end();
+                (o.x..e.x).map(move |x| Rectangle {
+                    o: XY { x: x,y: o.y, },
+                    s: XY { x: 1,y: e.y - o.y, },
+                })
+            }
+        }
+    );
+);
+
+macro_rules! impl_rectangle_float (
+    ($t:ty) => (
+        impl Rectangle<$t> {
+            pub fn inflate(&self,d: XY<$t>) -> Rectangle<$t> {
+                Rectangle {
+                    o: self.o - d,
+                    s: self.s + 2.0 * d,
+                }
+            }
+
+            pub fn deflate(&self,d: XY<$t>) -> Rectangle<$t> {
+                let c = self.center();
+                let x0 = (self.o.x + d.x).min(c.x);
+                let y0 = (self.o.y + d.y).min(c.y);
+                let x1 = (self.o.x + self.s.x - d.x).max(x0);
+                let y1 = (self.o.y + self.s.y - d.y).max(y0);
+                Rectangle {
+                    o: XY { x: x0,y: y0, },
+                    s: XY { x: x1 - x0,y: y1 - y0, },
+                }
+            }
+        }
+    );
+);
+
+// integer to float conversion is exact up to the float's precision, float to
+// integer conversion gives the smallest integer rectangle covering the float one,
+// a negative size becomes 0
+macro_rules! impl_rectangle_cast (
+    ($f:ty; $($i:ty),*) => (
+        $(
+            impl From<Rectangle<$i>> for Rectangle<$f> {
+                fn from(r: Rectangle<$i>) -> Rectangle<$f> {
+                    Rectangle {
+                        o: XY { x: r.o.x as $f,y: r.o.y as $f, },
+                        s: XY { x: r.s.x as $f,y: r.s.y as $f, },
+                    }
+                }
+            }
+
+            impl From<Rectangle<$f>> for Rectangle<$i> {
+                fn from(r: Rectangle<$f>) -> Rectangle<$i> {
+                    let x0 = r.o.x.floor() as $i;
+                    let y0 = r.o.y.floor() as $i;
+                    let x1 = ((r.o.x + r.s.x).ceil() as $i).max(x0);
+                    let y1 = ((r.o.y + r.s.y).ceil() as $i).max(y0);
+                    Rectangle {
+                        o: XY { x: x0,y: y0, },
+                        s: XY { x: x1 - x0,y: y1 - y0, },
+                    }
+                }
+            }
+        )*
+    );
+);
+
+impl_rectangle_cast!(f32; u16,i16,u32,i32,u64,i64,usize,isize);
+impl_rectangle_cast!(f64; u16,i16,u32,i32,u64,i64,usize,isize);
+
 /*impl<T> Zero for Rectangle<T> {
     fn zero() -> Rectangle<T> {
         Rectangle {
@@ -51,29 +312,459 @@ impl<T: fmt::Display> fmt::Debug for Rectangle<T> where XY<T>: fmt::Display {
 #[allow(non_camel_case_types)]
 pub type u16r = Rectangle<u16>;
 
+impl_rectangle!(u16);
+impl_rectangle_integer!(u16);
+
 #[allow(non_camel_case_types)]
 pub type i16r = Rectangle<i16>;
 
+impl_rectangle!(i16);
+impl_rectangle_integer!(i16);
+
 #[allow(non_camel_case_types)]
 pub type u32r = Rectangle<u32>;
 
+impl_rectangle!(u32);
+impl_rectangle_integer!(u32);
+
 #[allow(non_camel_case_types)]
 pub type i32r = Rectangle<i32>;
 
+impl_rectangle!(i32);
+impl_rectangle_integer!(i32);
+
 #[allow(non_camel_case_types)]
 pub type u64r = Rectangle<u64>;
 
+impl_rectangle!(u64);
+impl_rectangle_integer!(u64);
+
 #[allow(non_camel_case_types)]
 pub type i64r = Rectangle<i64>;
 
+impl_rectangle!(i64);
+impl_rectangle_integer!(i64);
+
 #[allow(non_camel_case_types)]
 pub type usizer = Rectangle<usize>;
 
+impl_rectangle!(usize);
+impl_rectangle_integer!(usize);
+
 #[allow(non_camel_case_types)]
 pub type isizer = Rectangle<isize>;
 
+impl_rectangle!(isize);
+impl_rectangle_integer!(isize);
+
 #[allow(non_camel_case_types)]
 pub type f32r = Rectangle<f32>;
 
+impl_rectangle!(f32);
+impl_rectangle_float!(f32);
+
 #[allow(non_camel_case_types)]
 pub type f64r = Rectangle<f64>;
+
+impl_rectangle!(f64);
+impl_rectangle_float!(f64);
+
+#[derive(Copy,Clone)]
+pub struct Volume<T> {
+    pub o: XYZ<T>,
+    pub s: XYZ<T>,
+}
+
+impl<T> Volume<T> {
+    pub fn new(o: XYZ<T>,s: XYZ<T>) -> Volume<T> {
+        Volume {
+            o: o,
+            s: s,
+        }
+    }
+}
+
+impl<T: cmp::PartialEq> cmp::PartialEq for Volume<T> {
+    fn eq(&self,other: &Volume<T>) -> bool {
+        (self.o.x == other.o.x)
+        && (self.o.y == other.o.y)
+        && (self.o.z == other.o.z)
+        && (self.s.x == other.s.x)
+        && (self.s.y == other.s.y)
+        && (self.s.z == other.s.z)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Volume<T> {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"({},{},{}; {}x{}x{})",self.o.x,self.o.y,self.o.z,self.s.x,self.s.y,self.s.z)
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for Volume<T> {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"({},{},{}; {}x{}x{})",self.o.x,self.o.y,self.o.z,self.s.x,self.s.y,self.s.z)
+    }
+}
+
+// Volumes are half-open: they contain o but not o + s.
+macro_rules! impl_volume (
+    ($t:ty) => (
+        impl Volume<$t> {
+            pub fn is_empty(&self) -> bool {
+                (self.s.x <= <$t>::zero())
+                || (self.s.y <= <$t>::zero())
+                || (self.s.z <= <$t>::zero())
+            }
+
+            pub fn volume(&self) -> $t {
+                self.s.x * self.s.y * self.s.z
+            }
+
+            pub fn center(&self) -> XYZ<$t> {
+                XYZ {
+                    x: self.o.x + self.s.x / (2 as $t),
+                    y: self.o.y + self.s.y / (2 as $t),
+                    z: self.o.z + self.s.z / (2 as $t),
+                }
+            }
+
+            pub fn contains(&self,p: XYZ<$t>) -> bool {
+                (p.x >= self.o.x) && (p.x - self.o.x < self.s.x)
+                && (p.y >= self.o.y) && (p.y - self.o.y < self.s.y)
+                && (p.z >= self.o.z) && (p.z - self.o.z < self.s.z)
+            }
+
+            pub fn intersect(&self,other: &Volume<$t>) -> Option<Volume<$t>> {
+                let x0 = self.o.x.max(other.o.x);
+                let y0 = self.o.y.max(other.o.y);
+                let z0 = self.o.z.max(other.o.z);
+                let x1 = (self.o.x + self.s.x).min(other.o.x + other.s.x);
+                let y1 = (self.o.y + self.s.y).min(other.o.y + other.s.y);
+                let z1 = (self.o.z + self.s.z).min(other.o.z + other.s.z);
+                if (x1 > x0) && (y1 > y0) && (z1 > z0) {
+                    Some(Volume {
+                        o: XYZ { x: x0,y: y0,z: z0, },
+                        s: XYZ { x: x1 - x0,y: y1 - y0,z: z1 - z0, },
+                    })
+                }
+                else {
+                    None
+                }
+            }
+
+            // smallest volume containing both, empty volumes are ignored
+            pub fn union(&self,other: &Volume<$t>) -> Volume<$t> {
+                if other.is_empty() {
+                    return *self;
+                }
+                if self.is_empty() {
+                    return *other;
+                }
+                let x0 = self.o.x.min(other.o.x);
+                let y0 = self.o.y.min(other.o.y);
+                let z0 = self.o.z.min(other.o.z);
+                let x1 = (self.o.x + self.s.x).max(other.o.x + other.s.x);
+                let y1 = (self.o.y + self.s.y).max(other.o.y + other.s.y);
+                let z1 = (self.o.z + self.s.z).max(other.o.z + other.s.z);
+                Volume {
+                    o: XYZ { x: x0,y: y0,z: z0, },
+                    s: XYZ { x: x1 - x0,y: y1 - y0,z: z1 - z0, },
+                }
+            }
+
+            pub fn corners(&self) -> impl Iterator<Item = XYZ<$t>> {
+                let o = self.o;
+                let e = XYZ {
+                    x: self.o.x + self.s.x,
+                    y: self.o.y + self.s.y,
+                    z: self.o.z + self.s.z,
+                };
+                (0..8).map(move |n| XYZ {
+                    x: if (n & 1) != 0 { e.x } else { o.x },
+                    y: if (n & 2) != 0 { e.y } else { o.y },
+                    z: if (n & 4) != 0 { e.z } else { o.z },
+                })
+            }
+        }
+    );
+);
+
+// expanding by a point makes the volume contain it, so the far side ends
+// one cell past the point
+macro_rules! impl_volume_integer (
+    ($t:ty) => (
+        impl Volume<$t> {
+            pub fn expand(&self,p: XYZ<$t>) -> Volume<$t> {
+                if self.is_empty() {
+                    return Volume {
+                        o: p,
+                        s: XYZ { x: 1,y: 1,z: 1, },
+                    };
+                }
+                let x0 = self.o.x.min(p.x);
+                let y0 = self.o.y.min(p.y);
+                let z0 = self.o.z.min(p.z);
+                let x1 = (self.o.x + self.s.x).max(p.x + 1);
+                let y1 = (self.o.y + self.s.y).max(p.y + 1);
+                let z1 = (self.o.z + self.s.z).max(p.z + 1);
+                Volume {
+                    o: XYZ { x: x0,y: y0,z: z0, },
+                    s: XYZ { x: x1 - x0,y: y1 - y0,z: z1 - z0, },
+                }
+            }
+        }
+    );
+);
+
+// as for integers, but the far side ends at the next float after the point,
+// and the size is pushed up one more step if rounding in the subtraction
+// lost the point again
+macro_rules! impl_volume_float (
+    ($t:ty) => (
+        impl Volume<$t> {
+            pub fn expand(&self,p: XYZ<$t>) -> Volume<$t> {
+                let (o,e) = if self.is_empty() {
+                    (p,p)
+                }
+                else {
+                    (
+                        XYZ { x: self.o.x.min(p.x),y: self.o.y.min(p.y),z: self.o.z.min(p.z), },
+                        XYZ { x: self.o.x + self.s.x,y: self.o.y + self.s.y,z: self.o.z + self.s.z, },
+                    )
+                };
+                let mut s = XYZ {
+                    x: e.x.max(p.x.next_up()) - o.x,
+                    y: e.y.max(p.y.next_up()) - o.y,
+                    z: e.z.max(p.z.next_up()) - o.z,
+                };
+                if p.x - o.x >= s.x {
+                    s.x = s.x.next_up();
+                }
+                if p.y - o.y >= s.y {
+                    s.y = s.y.next_up();
+                }
+                if p.z - o.z >= s.z {
+                    s.z = s.z.next_up();
+                }
+                Volume {
+                    o: o,
+                    s: s,
+                }
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type u16v = Volume<u16>;
+
+impl_volume!(u16);
+impl_volume_integer!(u16);
+
+#[allow(non_camel_case_types)]
+pub type i16v = Volume<i16>;
+
+impl_volume!(i16);
+impl_volume_integer!(i16);
+
+#[allow(non_camel_case_types)]
+pub type u32v = Volume<u32>;
+
+impl_volume!(u32);
+impl_volume_integer!(u32);
+
+#[allow(non_camel_case_types)]
+pub type i32v = Volume<i32>;
+
+impl_volume!(i32);
+impl_volume_integer!(i32);
+
+#[allow(non_camel_case_types)]
+pub type u64v = Volume<u64>;
+
+impl_volume!(u64);
+impl_volume_integer!(u64);
+
+#[allow(non_camel_case_types)]
+pub type i64v = Volume<i64>;
+
+impl_volume!(i64);
+impl_volume_integer!(i64);
+
+#[allow(non_camel_case_types)]
+pub type usizev = Volume<usize>;
+
+impl_volume!(usize);
+impl_volume_integer!(usize);
+
+#[allow(non_camel_case_types)]
+pub type isizev = Volume<isize>;
+
+impl_volume!(isize);
+impl_volume_integer!(isize);
+
+#[allow(non_camel_case_types)]
+pub type f32v = Volume<f32>;
+
+impl_volume!(f32);
+impl_volume_float!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64v = Volume<f64>;
+
+impl_volume!(f64);
+impl_volume_float!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_contains_points() {
+        let v = f32v::new(f32xyz::new(0.0,0.0,0.0),f32xyz::new(0.0,0.0,0.0));
+        let v = v.expand(f32xyz::new(1.0,2.0,3.0));
+        assert!(!v.is_empty());
+        assert!(v.contains(f32xyz::new(1.0,2.0,3.0)));
+        let v = v.expand(f32xyz::new(4.0,5.0,6.0));
+        assert!(v.contains(f32xyz::new(1.0,2.0,3.0)));
+        assert!(v.contains(f32xyz::new(4.0,5.0,6.0)));
+        assert!(v.contains(f32xyz::new(2.5,3.5,4.5)));
+        assert!(!v.contains(f32xyz::new(4.5,5.0,6.0)));
+        assert_eq!(v.o,f32xyz::new(1.0,2.0,3.0));
+
+        let v = u32v::new(u32xyz::new(0,0,0),u32xyz::new(0,0,0));
+        let v = v.expand(u32xyz::new(1,2,3)).expand(u32xyz::new(4,5,6));
+        assert_eq!(v,u32v::new(u32xyz::new(1,2,3),u32xyz::new(4,4,4)));
+        assert!(v.contains(u32xyz::new(4,5,6)));
+    }
+
+    #[test]
+    fn expand_keeps_far_points() {
+        // far apart magnitudes, where x1 - x0 rounds
+        let mut v = f64v::new(f64xyz::new(0.0,0.0,0.0),f64xyz::new(0.0,0.0,0.0));
+        let points = [
+            f64xyz::new(-1e-300,1e20,-3.0),
+            f64xyz::new(1e16,-7.5,0.1),
+            f64xyz::new(0.3,1e20 + 16384.0,-1e-9),
+        ];
+        for p in points.iter() {
+            v = v.expand(*p);
+            assert!(v.contains(*p));
+        }
+        let mut v = f32v::new(f32xyz::new(0.0,0.0,0.0),f32xyz::new(0.0,0.0,0.0));
+        for n in 0..1000 {
+            let p = f32xyz::new((n as f32 * 0.37).sin() * 1e5,(n as f32).cos(),n as f32 * 1e-3);
+            v = v.expand(p);
+            assert!(v.contains(p));
+        }
+    }
+
+    #[test]
+    fn cast_negative_size() {
+        let r = u32r::from(f32r::new(f32xy::new(5.0,0.0),f32xy::new(-2.0,1.0)));
+        assert_eq!(r,u32r::new(u32xy::new(5,0),u32xy::new(0,1)));
+        assert!(r.is_empty());
+        let r = i32r::from(f64r::new(f64xy::new(-1.5,2.0),f64xy::new(3.0,-0.5)));
+        assert_eq!(r,i32r::new(i32xy::new(-2,2),i32xy::new(4,0)));
+        let r = u16r::from(f32r::new(f32xy::new(0.5,1.5),f32xy::new(2.0,2.0)));
+        assert_eq!(r,u16r::new(u16xy::new(0,1),u16xy::new(3,3)));
+    }
+
+    fn ir(x: i32,y: i32,w: i32,h: i32) -> i32r {
+        i32r::new(i32xy::new(x,y),i32xy::new(w,h))
+    }
+
+    #[test]
+    fn points() {
+        let p: Vec<i32xy> = ir(-1,-2,3,2).points().collect();
+        let expected = [(-1,-2),(0,-2),(1,-2),(-1,-1),(0,-1),(1,-1)];
+        assert_eq!(p.len(),expected.len());
+        for (a,b) in p.iter().zip(expected.iter()) {
+            assert_eq!(*a,i32xy::new(b.0,b.1));
+        }
+        assert_eq!(u16r::new(u16xy::new(0,0),u16xy::new(7,5)).points().count(),35);
+        assert_eq!(ir(0,0,1,1).points().collect::<Vec<i32xy>>(),vec![i32xy::new(0,0)]);
+        assert_eq!(ir(3,3,0,5).points().count(),0);
+        assert_eq!(ir(3,3,5,-2).points().count(),0);
+    }
+
+    #[test]
+    fn tiles() {
+        let t: Vec<i32r> = ir(-5,10,10,7).tiles(i32xy::new(4,4)).collect();
+        let expected = [
+            ir(-5,10,4,4),ir(-1,10,4,4),ir(3,10,2,4),
+            ir(-5,14,4,3),ir(-1,14,4,3),ir(3,14,2,3),
+        ];
+        assert_eq!(t,expected.to_vec());
+        // exact multiples are not clipped and get no extra tiles
+        let t: Vec<u32r> = u32r::new(u32xy::new(0,0),u32xy::new(8,4)).tiles(u32xy::new(4,4)).collect();
+        assert_eq!(t.len(),2);
+        assert!(t.iter().all(|r| r.s == u32xy::new(4,4)));
+        // tiles larger than the rectangle give the rectangle itself
+        assert_eq!(ir(1,2,3,4).tiles(i32xy::new(100,100)).collect::<Vec<i32r>>(),vec![ir(1,2,3,4)]);
+        assert_eq!(ir(0,0,10,10).tiles(i32xy::new(0,4)).count(),0);
+        assert_eq!(ir(0,0,10,10).tiles(i32xy::new(4,-1)).count(),0);
+        assert_eq!(u16r::new(u16xy::new(0,0),u16xy::new(10,10)).tiles(u16xy::new(0,0)).count(),0);
+        assert_eq!(ir(0,0,0,10).tiles(i32xy::new(4,4)).count(),0);
+        assert_eq!(ir(0,0,10,-10).tiles(i32xy::new(4,4)).count(),0);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let r = ir(-3,-2,4,3);
+        let rows: Vec<i32r> = r.rows().collect();
+        assert_eq!(rows,vec![ir(-3,-2,4,1),ir(-3,-1,4,1),ir(-3,0,4,1)]);
+        let columns: Vec<i32r> = r.columns().collect();
+        assert_eq!(columns,vec![ir(-3,-2,1,3),ir(-2,-2,1,3),ir(-1,-2,1,3),ir(0,-2,1,3)]);
+        assert_eq!(ir(0,0,0,3).rows().count(),0);
+        assert_eq!(ir(0,0,0,3).columns().count(),0);
+        assert_eq!(ir(0,0,3,-1).rows().count(),0);
+        assert_eq!(ir(0,0,3,-1).columns().count(),0);
+    }
+
+    #[test]
+    fn unsigned_inflate_deflate() {
+        let u = |x: u16,y: u16,w: u16,h: u16| u16r::new(u16xy::new(x,y),u16xy::new(w,h));
+        assert_eq!(u(0,0,10,10).inflate(u16xy::new(3,3)),u(0,0,13,13));
+        assert_eq!(u(2,0,10,10).inflate(u16xy::new(3,1)),u(0,0,15,11));
+        assert_eq!(u(65530,0,5,5).inflate(u16xy::new(10,0)),u(65520,0,15,5));
+        // more than half the size collapses to the center instead of wrapping
+        assert_eq!(u(0,0,10,6).deflate(u16xy::new(6,4)),u(5,3,0,0));
+        assert_eq!(u(4,4,3,3).deflate(u16xy::new(100,1)),u(5,5,0,1));
+        assert!(u(4,4,3,3).deflate(u16xy::new(2,2)).is_empty());
+        assert_eq!(ir(-4,-4,8,8).deflate(i32xy::new(5,5)),ir(0,0,0,0));
+        assert_eq!(ir(-4,-4,8,8).deflate(i32xy::new(2,3)),ir(-2,-1,4,2));
+    }
+
+    #[test]
+    fn subtract_tiles_the_difference() {
+        let a = u16r::new(u16xy::new(2,2),u16xy::new(6,6));
+        let others = [
+            (u16r::new(u16xy::new(0,0),u16xy::new(10,10)),0),
+            (u16r::new(u16xy::new(20,20),u16xy::new(2,2)),1),
+            (u16r::new(u16xy::new(0,0),u16xy::new(5,10)),1),
+            (u16r::new(u16xy::new(0,0),u16xy::new(5,5)),2),
+            (u16r::new(u16xy::new(0,4),u16xy::new(10,2)),2),
+            (u16r::new(u16xy::new(4,0),u16xy::new(2,5)),3),
+            (u16r::new(u16xy::new(4,4),u16xy::new(2,2)),4),
+            (u16r::new(u16xy::new(2,2),u16xy::new(0,3)),1),
+        ];
+        for (b,count) in others.iter() {
+            let pieces = a.subtract(b);
+            assert_eq!(pieces.len(),*count,"{}",b);
+            assert!(pieces.iter().all(|p| !p.is_empty() && a.contains_rect(p)));
+            for y in 0..12 {
+                for x in 0..12 {
+                    let p = u16xy::new(x,y);
+                    let inside = pieces.iter().filter(|r| r.contains(p)).count();
+                    let expected = if a.contains(p) && !b.contains(p) { 1 } else { 0 };
+                    assert_eq!(inside,expected,"{} {}",b,p);
+                }
+            }
+        }
+        let a = u16r::new(u16xy::new(0,0),u16xy::new(3,3));
+        assert_eq!(a.intersect(&u16r::new(u16xy::new(3,0),u16xy::new(2,2))),None);
+        assert_eq!(a.union(&u16r::new(u16xy::new(5,5),u16xy::new(0,0))),a);
+    }
+}
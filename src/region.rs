@@ -0,0 +1,395 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Regions, sets of disjoint rectangles
+
+use std::{cmp,fmt,slice};
+use crate::*;
+
+// The rectangles are kept in canonical banded form, like X11 regions:
+// - rectangles are sorted by y and then by x
+// - rectangles in the same band have the same y and height and do not touch
+// - vertically adjacent bands with the same horizontal spans are merged
+// so two regions covering the same area have the same rectangles.
+#[derive(Clone)]
+pub struct Region<T> {
+    rects: Vec<Rectangle<T>>,
+}
+
+// $cmp orders the coordinates, total_cmp for floats so NaN does not panic
+macro_rules! impl_region (
+    ($t:ty,$cmp:ident) => (
+        impl Region<$t> {
+            pub fn new() -> Region<$t> {
+                Region {
+                    rects: Vec::new(),
+                }
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.rects.is_empty()
+            }
+
+            pub fn rects(&self) -> &[Rectangle<$t>] {
+                &self.rects
+            }
+
+            pub fn iter(&self) -> slice::Iter<'_,Rectangle<$t>> {
+                self.rects.iter()
+            }
+
+            pub fn bounding_box(&self) -> Rectangle<$t> {
+                if self.rects.is_empty() {
+                    return Rectangle {
+                        o: XY { x: <$t>::zero(),y: <$t>::zero(), },
+                        s: XY { x: <$t>::zero(),y: <$t>::zero(), },
+                    };
+                }
+                let first = &self.rects[0];
+                let last = &self.rects[self.rects.len() - 1];
+                let mut x0 = first.o.x;
+                let mut x1 = first.o.x + first.s.x;
+                for r in self.rects.iter() {
+                    x0 = x0.min(r.o.x);
+                    x1 = x1.max(r.o.x + r.s.x);
+                }
+                let y0 = first.o.y;
+                let y1 = last.o.y + last.s.y;
+                Rectangle {
+                    o: XY { x: x0,y: y0, },
+                    s: XY { x: x1 - x0,y: y1 - y0, },
+                }
+            }
+
+            pub fn contains(&self,p: XY<$t>) -> bool {
+                self.rects.iter().any(|r| r.contains(p))
+            }
+
+            pub fn contains_rect(&self,r: &Rectangle<$t>) -> bool {
+                Region::<$t>::from(*r).subtract(self).is_empty()
+            }
+
+            pub fn translate(&self,d: XY<$t>) -> Region<$t> {
+                Region {
+                    rects: self.rects.iter().map(|r| r.translate(d)).collect(),
+                }
+            }
+
+            pub fn union(&self,other: &Region<$t>) -> Region<$t> {
+                self.combine(other,|a,b| a || b)
+            }
+
+            pub fn intersect(&self,other: &Region<$t>) -> Region<$t> {
+                self.combine(other,|a,b| a && b)
+            }
+
+            pub fn subtract(&self,other: &Region<$t>) -> Region<$t> {
+                self.combine(other,|a,b| a && !b)
+            }
+
+            pub fn xor(&self,other: &Region<$t>) -> Region<$t> {
+                self.combine(other,|a,b| a != b)
+            }
+
+            // (y0,y1,spans) for each band
+            fn bands(&self) -> Vec<($t,$t,Vec<($t,$t)>)> {
+                let mut bands: Vec<($t,$t,Vec<($t,$t)>)> = Vec::new();
+                for r in self.rects.iter() {
+                    let y0 = r.o.y;
+                    let y1 = r.o.y + r.s.y;
+                    let span = (r.o.x,r.o.x + r.s.x);
+                    match bands.last_mut() {
+                        Some(band) if (band.0 == y0) && (band.1 == y1) => band.2.push(span),
+                        _ => bands.push((y0,y1,vec![span])),
+                    }
+                }
+                bands
+            }
+
+            fn combine_spans(a: &[($t,$t)],b: &[($t,$t)],op: fn(bool,bool) -> bool) -> Vec<($t,$t)> {
+                let mut xs: Vec<$t> = a.iter().chain(b.iter()).flat_map(|s| vec![s.0,s.1]).collect();
+                xs.sort_by(|p,q| p.$cmp(q));
+                xs.dedup();
+                let mut ia = 0;
+                let mut ib = 0;
+                let mut spans: Vec<($t,$t)> = Vec::new();
+                for w in xs.windows(2) {
+                    let (x0,x1) = (w[0],w[1]);
+                    while (ia < a.len()) && (a[ia].1 <= x0) {
+                        ia += 1;
+                    }
+                    while (ib < b.len()) && (b[ib].1 <= x0) {
+                        ib += 1;
+                    }
+                    let ina = (ia < a.len()) && (a[ia].0 <= x0);
+                    let inb = (ib < b.len()) && (b[ib].0 <= x0);
+                    if op(ina,inb) {
+                        match spans.last_mut() {
+                            Some(span) if span.1 == x0 => span.1 = x1,
+                            _ => spans.push((x0,x1)),
+                        }
+                    }
+                }
+                spans
+            }
+
+            // split both regions at every band edge of either, combine the spans
+            // in each slice and merge slices that end up the same
+            fn combine(&self,other: &Region<$t>,op: fn(bool,bool) -> bool) -> Region<$t> {
+                let a = self.bands();
+                let b = other.bands();
+                let mut ys: Vec<$t> = a.iter().chain(b.iter()).flat_map(|band| vec![band.0,band.1]).collect();
+                ys.sort_by(|p,q| p.$cmp(q));
+                ys.dedup();
+                let none: Vec<($t,$t)> = Vec::new();
+                let mut ia = 0;
+                let mut ib = 0;
+                let mut bands: Vec<($t,$t,Vec<($t,$t)>)> = Vec::new();
+                for w in ys.windows(2) {
+                    let (y0,y1) = (w[0],w[1]);
+                    while (ia < a.len()) && (a[ia].1 <= y0) {
+                        ia += 1;
+                    }
+                    while (ib < b.len()) && (b[ib].1 <= y0) {
+                        ib += 1;
+                    }
+                    let sa = if (ia < a.len()) && (a[ia].0 <= y0) {
+                        &a[ia].2
+                    }
+                    else {
+                        &none
+                    };
+                    let sb = if (ib < b.len()) && (b[ib].0 <= y0) {
+                        &b[ib].2
+                    }
+                    else {
+                        &none
+                    };
+                    let spans = Region::<$t>::combine_spans(sa,sb,op);
+                    if spans.is_empty() {
+                        continue;
+                    }
+                    match bands.last_mut() {
+                        Some(band) if (band.1 == y0) && (band.2 == spans) => band.1 = y1,
+                        _ => bands.push((y0,y1,spans)),
+                    }
+                }
+                let mut rects = Vec::new();
+                for (y0,y1,spans) in bands {
+                    for (x0,x1) in spans {
+                        rects.push(Rectangle {
+                            o: XY { x: x0,y: y0, },
+                            s: XY { x: x1 - x0,y: y1 - y0, },
+                        });
+                    }
+                }
+                Region {
+                    rects: rects,
+                }
+            }
+        }
+
+        impl Default for Region<$t> {
+            fn default() -> Region<$t> {
+                Region::<$t>::new()
+            }
+        }
+
+        impl From<Rectangle<$t>> for Region<$t> {
+            fn from(r: Rectangle<$t>) -> Region<$t> {
+                if r.is_empty() {
+                    Region::<$t>::new()
+                }
+                else {
+                    Region {
+                        rects: vec![r],
+                    }
+                }
+            }
+        }
+
+        impl<'a> IntoIterator for &'a Region<$t> {
+            type Item = &'a Rectangle<$t>;
+            type IntoIter = slice::Iter<'a,Rectangle<$t>>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.rects.iter()
+            }
+        }
+
+        impl cmp::PartialEq for Region<$t> {
+            fn eq(&self,other: &Region<$t>) -> bool {
+                self.rects == other.rects
+            }
+        }
+
+        impl fmt::Display for Region<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"[")?;
+                for (n,r) in self.rects.iter().enumerate() {
+                    if n > 0 {
+                        write!(f,", ")?;
+                    }
+                    write!(f,"{}",r)?;
+                }
+                write!(f,"]")
+            }
+        }
+
+        impl fmt::Debug for Region<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(self,f)
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type u16region = Region<u16>;
+
+impl_region!(u16,cmp);
+
+#[allow(non_camel_case_types)]
+pub type i16region = Region<i16>;
+
+impl_region!(i16,cmp);
+
+#[allow(non_camel_case_types)]
+pub type u32region = Region<u32>;
+
+impl_region!(u32,cmp);
+
+#[allow(non_camel_case_types)]
+pub type i32region = Region<i32>;
+
+impl_region!(i32,cmp);
+
+#[allow(non_camel_case_types)]
+pub type u64region = Region<u64>;
+
+impl_region!(u64,cmp);
+
+#[allow(non_camel_case_types)]
+pub type i64region = Region<i64>;
+
+impl_region!(i64,cmp);
+
+#[allow(non_camel_case_types)]
+pub type usizeregion = Region<usize>;
+
+impl_region!(usize,cmp);
+
+#[allow(non_camel_case_types)]
+pub type isizeregion = Region<isize>;
+
+impl_region!(isize,cmp);
+
+#[allow(non_camel_case_types)]
+pub type f32region = Region<f32>;
+
+impl_region!(f32,total_cmp);
+
+#[allow(non_camel_case_types)]
+pub type f64region = Region<f64>;
+
+impl_region!(f64,total_cmp);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32,y: i32,w: i32,h: i32) -> Region<i32> {
+        Region::<i32>::from(i32r::new(i32xy::new(x,y),i32xy::new(w,h)))
+    }
+
+    // the banding rules from the comment on Region
+    fn assert_canonical(region: &Region<i32>) {
+        let rects = region.rects();
+        for r in rects.iter() {
+            assert!(!r.is_empty());
+        }
+        for w in rects.windows(2) {
+            let (a,b) = (&w[0],&w[1]);
+            if a.o.y == b.o.y {
+                assert_eq!(a.s.y,b.s.y);
+                assert!(a.o.x + a.s.x < b.o.x);
+            }
+            else {
+                assert!(a.o.y + a.s.y <= b.o.y);
+            }
+        }
+        let bands = region.bands();
+        for w in bands.windows(2) {
+            assert!((w[0].1 != w[1].0) || (w[0].2 != w[1].2));
+        }
+    }
+
+    // small deterministic generator, so the tests need no dependencies
+    fn rects(seed: u32,n: usize) -> Vec<Region<i32>> {
+        let mut state = seed;
+        let mut next = move |m: u32| {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            ((state >> 16) % m) as i32
+        };
+        (0..n).map(|_| rect(next(20),next(20),next(8),next(8))).collect()
+    }
+
+    #[test]
+    fn vertical_merge() {
+        let r = rect(0,0,10,5).union(&rect(0,5,10,5));
+        assert_eq!(r.rects(),&[i32r::new(i32xy::new(0,0),i32xy::new(10,10))]);
+        let r = rect(0,0,10,5).union(&rect(0,5,9,5));
+        assert_eq!(r.rects().len(),2);
+        assert_canonical(&r);
+    }
+
+    #[test]
+    fn horizontal_merge() {
+        let r = rect(0,0,5,5).union(&rect(5,0,5,5));
+        assert_eq!(r.rects(),&[i32r::new(i32xy::new(0,0),i32xy::new(10,5))]);
+        let r = rect(0,0,5,5).union(&rect(6,0,5,5));
+        assert_eq!(r.rects().len(),2);
+        assert_canonical(&r);
+    }
+
+    #[test]
+    fn same_area_same_rects() {
+        // an L shape built from different pieces
+        let a = rect(0,0,10,5).union(&rect(0,5,5,5));
+        let b = rect(0,0,5,10).union(&rect(5,0,5,5));
+        let c = rect(0,0,10,10).subtract(&rect(5,5,5,5));
+        assert_eq!(a,b);
+        assert_eq!(a,c);
+        assert_canonical(&a);
+    }
+
+    #[test]
+    fn union_commutes() {
+        for seed in 0..50 {
+            let rs = rects(seed,8);
+            let mut forward = Region::<i32>::new();
+            for r in rs.iter() {
+                forward = forward.union(r);
+                assert_canonical(&forward);
+            }
+            let mut backward = Region::<i32>::new();
+            for r in rs.iter().rev() {
+                backward = r.union(&backward);
+            }
+            assert_eq!(forward,backward);
+            for (a,b) in rs.iter().zip(rs.iter().skip(1)) {
+                assert_eq!(a.union(b),b.union(a));
+                assert_eq!(a.intersect(b),b.intersect(a));
+                assert_eq!(a.xor(b),b.xor(a));
+                assert_canonical(&a.subtract(b));
+            }
+        }
+    }
+
+    #[test]
+    fn nan_does_not_panic() {
+        let a = f32region::from(f32r::new(f32xy::new(0.0,0.0),f32xy::new(4.0,4.0)));
+        let b = f32region::from(f32r::new(f32xy::new(f32::NAN,1.0),f32xy::new(2.0,f32::NAN)));
+        let _ = a.union(&b);
+        let _ = a.subtract(&b);
+        let _ = b.intersect(&a);
+    }
+}
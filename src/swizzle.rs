@@ -0,0 +1,57 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Swizzling
+
+//! Getters like v.zyx() or v.xxyy() build a new vector from the named
+//! components. Setters like v.set_zx(a) only exist when no component repeats:
+//!
+//! ```compile_fail
+//! use math::*;
+//! let mut v = f32xyz::new(1.0,2.0,3.0);
+//! v.set_xx(f32xy::new(4.0,5.0));
+//! ```
+//!
+//! The swizzle macro generates all of them from the list of components.
+
+use crate::*;
+use math_macros::swizzle;
+
+swizzle!(XY: x,y);
+swizzle!(XYZ: x,y,z);
+swizzle!(XYZW: x,y,z,w);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn getters() {
+        let v = i32xy::new(1,2);
+        assert_eq!(v.yx(),i32xy::new(2,1));
+        assert_eq!(v.xyy(),i32xyz::new(1,2,2));
+        assert_eq!(v.xxyy(),i32xyzw::new(1,1,2,2));
+        let v = i32xyz::new(1,2,3);
+        assert_eq!(v.zx(),i32xy::new(3,1));
+        assert_eq!(v.zyx(),i32xyz::new(3,2,1));
+        assert_eq!(v.xzzy(),i32xyzw::new(1,3,3,2));
+        let v = i32xyzw::new(1,2,3,4);
+        assert_eq!(v.wy(),i32xy::new(4,2));
+        assert_eq!(v.www(),i32xyz::new(4,4,4));
+        assert_eq!(v.wzyx(),i32xyzw::new(4,3,2,1));
+    }
+
+    #[test]
+    fn setters() {
+        let mut v = i32xy::new(1,2);
+        v.set_yx(i32xy::new(5,6));
+        assert_eq!(v,i32xy::new(6,5));
+        let mut v = i32xyz::new(1,2,3);
+        v.set_zx(i32xy::new(5,6));
+        assert_eq!(v,i32xyz::new(6,2,5));
+        let mut v = i32xyzw::new(1,2,3,4);
+        v.set_wxz(i32xyz::new(5,6,7));
+        assert_eq!(v,i32xyzw::new(6,2,7,5));
+        v.set_yzwx(i32xyzw::new(8,9,10,11));
+        assert_eq!(v,i32xyzw::new(11,8,9,10));
+    }
+}
@@ -0,0 +1,173 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Tone mapping and HDR utilities
+
+use crate::*;
+
+// All operators take scene linear RGB (Rec.709 primaries) and return display
+// linear RGB, ready for to_srgb. Negative components are treated as 0. The
+// result is in [0,1], except for reinhard_luminance, which only limits the
+// luminance to 1 and leaves clipping of saturated colors to the caller.
+macro_rules! impl_tonemap (
+    ($t:ty) => (
+        impl RGB<$t> {
+            pub fn luminance_rec709(&self) -> $t {
+                0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+            }
+
+            pub fn luminance_rec2020(&self) -> $t {
+                0.2627 * self.r + 0.6780 * self.g + 0.0593 * self.b
+            }
+
+            // scale by 2^ev
+            pub fn expose(&self,ev: $t) -> RGB<$t> {
+                *self * ev.exp2()
+            }
+
+            // exposure scale for a camera at EV100, with the usual 1.2 lens and
+            // sensor factor, so a 100% white lands at 1.0
+            pub fn exposure_from_ev100(ev100: $t) -> $t {
+                1.0 / (1.2 * ev100.exp2())
+            }
+
+            fn map_components(&self,f: impl Fn($t) -> $t) -> RGB<$t> {
+                RGB {
+                    r: f(self.r.max(0.0)),
+                    g: f(self.g.max(0.0)),
+                    b: f(self.b.max(0.0)),
+                }
+            }
+
+            // c / (1 + c)
+            pub fn reinhard(&self) -> RGB<$t> {
+                self.map_components(|c| c / (1.0 + c))
+            }
+
+            // like reinhard, but white (and up) maps to 1
+            pub fn reinhard_extended(&self,white: $t) -> RGB<$t> {
+                let w2 = white * white;
+                self.map_components(|c| (c * (1.0 + c / w2) / (1.0 + c)).min(1.0))
+            }
+
+            // reinhard_extended on the Rec.709 luminance, keeps the hue but
+            // can push components above 1
+            pub fn reinhard_luminance(&self,white: $t) -> RGB<$t> {
+                let c = self.map_components(|c| c);
+                let l = c.luminance_rec709();
+                if l <= 0.0 {
+                    return RGB::<$t>::zero();
+                }
+                let lo = (l * (1.0 + l / (white * white)) / (1.0 + l)).min(1.0);
+                c * (lo / l)
+            }
+
+            // Narkowicz's fit of the ACES reference rendering transform, the
+            // 0.6 scale brings the input to the exposure the fit was made for
+            pub fn aces(&self) -> RGB<$t> {
+                self.map_components(|c| {
+                    let x = 0.6 * c;
+                    ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0,1.0)
+                })
+            }
+
+            // Hable's Uncharted 2 curve with exposure bias 2 and white point 11.2
+            pub fn hable(&self) -> RGB<$t> {
+                let f = |x: $t| {
+                    let (a,b,c,d,e,f) = (0.15,0.50,0.10,0.20,0.02,0.30);
+                    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+                };
+                let w = f(11.2);
+                self.map_components(|c| (f(2.0 * c) / w).clamp(0.0,1.0))
+            }
+
+            // Sobotka's AgX base look, with the polynomial fit of its sigmoid
+            pub fn agx(&self) -> RGB<$t> {
+                let inset = Mat3x3::<$t>::new(
+                    XYZ::<$t>::new(0.842479062253094,0.0423282422610123,0.0423756549057051),
+                    XYZ::<$t>::new(0.0784335999999992,0.878468636469772,0.0784336),
+                    XYZ::<$t>::new(0.0792237451477643,0.0791661274605434,0.879142973793104),
+                );
+                let outset = Mat3x3::<$t>::new(
+                    XYZ::<$t>::new(1.19687900512017,-0.0528968517574562,-0.0529716355144438),
+                    XYZ::<$t>::new(-0.0980208811401368,1.15190312990417,-0.0980434501171241),
+                    XYZ::<$t>::new(-0.0990297440797205,-0.0989611768448433,1.15107367264116),
+                );
+                let min_ev = -12.47393;
+                let max_ev = 4.026069;
+                let c = self.map_components(|c| c);
+                let v = inset * XYZ::<$t>::new(c.r,c.g,c.b);
+                let curve = |x: $t| {
+                    let x = (x.max(0.0).log2().clamp(min_ev,max_ev) - min_ev) / (max_ev - min_ev);
+                    let x2 = x * x;
+                    let x4 = x2 * x2;
+                    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232
+                };
+                let v = outset * XYZ::<$t>::new(curve(v.x),curve(v.y),curve(v.z));
+                RGB {
+                    r: v.x.max(0.0).powf(2.2).min(1.0),
+                    g: v.y.max(0.0).powf(2.2).min(1.0),
+                    b: v.z.max(0.0).powf(2.2).min(1.0),
+                }
+            }
+        }
+    );
+);
+
+impl_tonemap!(f32);
+impl_tonemap!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_range() {
+        let levels = [0.0,0.001,0.18,0.5,1.0,2.0,11.2,100.0,1e6];
+        for &r in levels.iter() {
+            for &g in levels.iter() {
+                for &b in levels.iter() {
+                    let c = f64rgb::new(r,g,b);
+                    let mapped = [c.reinhard(),c.reinhard_extended(4.0),c.aces(),c.hable(),c.agx()];
+                    for m in mapped.iter() {
+                        for &v in [m.r,m.g,m.b].iter() {
+                            assert!((0.0..=1.0).contains(&v),"{}: {}",c,m);
+                        }
+                    }
+                    let m = c.reinhard_luminance(4.0);
+                    assert!(m.luminance_rec709() <= 1.0 + 1e-12,"{}: {}",c,m);
+                }
+            }
+        }
+        // a saturated color keeps its hue and goes above 1
+        let m = f64rgb::new(0.0,0.0,50.0).reinhard_luminance(4.0);
+        assert!(m.b > 1.0);
+    }
+
+    fn close(a: f64rgb,b: f64rgb,tol: f64) -> bool {
+        ((a.r - b.r).abs() < tol) && ((a.g - b.g).abs() < tol) && ((a.b - b.b).abs() < tol)
+    }
+
+    #[test]
+    fn known_values() {
+        let white = f64rgb::new(1.0,1.0,1.0);
+        assert!((white.luminance_rec709() - 1.0).abs() < 1e-12);
+        assert!((white.luminance_rec2020() - 1.0).abs() < 1e-12);
+        let c = f64rgb::new(0.1,0.5,2.0);
+        assert!(close(c.expose(1.0),f64rgb::new(0.2,1.0,4.0),1e-12));
+        assert!(close(c.expose(-2.0),f64rgb::new(0.025,0.125,0.5),1e-12));
+        assert!(close(f64rgb::new(1.0,3.0,0.0).reinhard(),f64rgb::new(0.5,0.75,0.0),1e-12));
+        // the white point maps to 1
+        for &w in [1.0,4.0,11.2].iter() {
+            assert!(close(f64rgb::new(w,w,w).reinhard_extended(w),white,1e-12));
+        }
+        // exposure bias 2 puts 5.6 on the white point
+        assert!(close(f64rgb::new(5.6,5.6,5.6).hable(),white,1e-12));
+        assert!(close(f64rgb::zero().aces(),f64rgb::zero(),1e-12));
+        // the fit maps 1 to 2.54 / 3.16 before the 0.6 scale
+        let x = 1.0 / 0.6;
+        assert!(close(f64rgb::new(x,x,x).aces(),f64rgb::new(0.803797,0.803797,0.803797),1e-6));
+        // mid-gray against the reference AgX shader
+        let m = f64rgb::new(0.18,0.18,0.18).agx();
+        assert!(close(m,f64rgb::new(0.2144674,0.2145327,0.2145367),1e-6),"{}",m);
+    }
+}
@@ -0,0 +1,387 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Transfer functions and encoded colors
+
+use std::{cmp,fmt,ops};
+use crate::*;
+
+// Transfer functions relate encoded values to linear light. encode applies
+// the curve, decode is its exact inverse. For sRGB and Display-P3 (which uses
+// the sRGB curve) that is the display EOTF.
+// Rec709 and Rec2020 are the camera OETF of their specifications, so decode
+// gives back scene light and is not the display EOTF. Both use the Rec.2020
+// constants (Rec.709 rounds them to 1.099 and 0.018, which leaves a small jump
+// at the joint), so the two variants give identical results and only name the
+// standard. BT1886 is the Rec.709/Rec.2020 display EOTF, a pure 2.4 gamma for
+// a display with zero black level and white at 1.
+// All curves are mirrored around 0, so out of gamut negative values survive a
+// round trip.
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum TransferFunction {
+    Linear,
+    SRGB,
+    Rec709,
+    Rec2020,
+    BT1886,
+    DisplayP3,
+}
+
+// gamma encoded sRGB, no arithmetic on purpose
+#[derive(Copy,Clone)]
+pub struct SRGB<T>(pub RGB<T>);
+
+#[derive(Copy,Clone)]
+pub struct SRGBA<T>(pub RGBA<T>);
+
+// linear light with sRGB primaries
+#[derive(Copy,Clone)]
+pub struct LinearRGB<T>(pub RGB<T>);
+
+#[derive(Copy,Clone)]
+pub struct LinearRGBA<T>(pub RGBA<T>);
+
+macro_rules! impl_transfer (
+    ($t:ty) => (
+        impl RGB<$t> {
+            pub fn decode_component(tf: TransferFunction,v: $t) -> $t {
+                let a = v.abs();
+                let l = match tf {
+                    TransferFunction::Linear => a,
+                    TransferFunction::SRGB | TransferFunction::DisplayP3 => if a <= 0.04045 {
+                        a / 12.92
+                    }
+                    else {
+                        ((a + 0.055) / 1.055).powf(2.4)
+                    },
+                    TransferFunction::Rec709 | TransferFunction::Rec2020 => if a < 4.5 * 0.018053968510807 {
+                        a / 4.5
+                    }
+                    else {
+                        ((a + 0.09929682680944) / 1.09929682680944).powf(1.0 / 0.45)
+                    },
+                    TransferFunction::BT1886 => a.powf(2.4),
+                };
+                l.copysign(v)
+            }
+
+            pub fn encode_component(tf: TransferFunction,l: $t) -> $t {
+                let a = l.abs();
+                let v = match tf {
+                    TransferFunction::Linear => a,
+                    TransferFunction::SRGB | TransferFunction::DisplayP3 => if a <= 0.0031308 {
+                        12.92 * a
+                    }
+                    else {
+                        1.055 * a.powf(1.0 / 2.4) - 0.055
+                    },
+                    TransferFunction::Rec709 | TransferFunction::Rec2020 => if a < 0.018053968510807 {
+                        4.5 * a
+                    }
+                    else {
+                        1.09929682680944 * a.powf(0.45) - 0.09929682680944
+                    },
+                    TransferFunction::BT1886 => a.powf(1.0 / 2.4),
+                };
+                v.copysign(l)
+            }
+
+            pub fn decode(&self,tf: TransferFunction) -> RGB<$t> {
+                RGB {
+                    r: RGB::<$t>::decode_component(tf,self.r),
+                    g: RGB::<$t>::decode_component(tf,self.g),
+                    b: RGB::<$t>::decode_component(tf,self.b),
+                }
+            }
+
+            pub fn encode(&self,tf: TransferFunction) -> RGB<$t> {
+                RGB {
+                    r: RGB::<$t>::encode_component(tf,self.r),
+                    g: RGB::<$t>::encode_component(tf,self.g),
+                    b: RGB::<$t>::encode_component(tf,self.b),
+                }
+            }
+
+            pub fn to_linear(&self) -> RGB<$t> {
+                self.decode(TransferFunction::SRGB)
+            }
+
+            pub fn to_srgb(&self) -> RGB<$t> {
+                self.encode(TransferFunction::SRGB)
+            }
+        }
+
+        // alpha is always linear
+        impl RGBA<$t> {
+            pub fn decode(&self,tf: TransferFunction) -> RGBA<$t> {
+                RGBA {
+                    r: RGB::<$t>::decode_component(tf,self.r),
+                    g: RGB::<$t>::decode_component(tf,self.g),
+                    b: RGB::<$t>::decode_component(tf,self.b),
+                    a: self.a,
+                }
+            }
+
+            pub fn encode(&self,tf: TransferFunction) -> RGBA<$t> {
+                RGBA {
+                    r: RGB::<$t>::encode_component(tf,self.r),
+                    g: RGB::<$t>::encode_component(tf,self.g),
+                    b: RGB::<$t>::encode_component(tf,self.b),
+                    a: self.a,
+                }
+            }
+
+            pub fn to_linear(&self) -> RGBA<$t> {
+                self.decode(TransferFunction::SRGB)
+            }
+
+            pub fn to_srgb(&self) -> RGBA<$t> {
+                self.encode(TransferFunction::SRGB)
+            }
+        }
+
+        impl SRGB<$t> {
+            pub fn new(r: $t,g: $t,b: $t) -> SRGB<$t> {
+                SRGB(RGB::<$t>::new(r,g,b))
+            }
+
+            pub fn to_linear(&self) -> LinearRGB<$t> {
+                LinearRGB(self.0.to_linear())
+            }
+        }
+
+        impl SRGBA<$t> {
+            pub fn new(r: $t,g: $t,b: $t,a: $t) -> SRGBA<$t> {
+                SRGBA(RGBA::<$t>::new(r,g,b,a))
+            }
+
+            pub fn to_linear(&self) -> LinearRGBA<$t> {
+                LinearRGBA(self.0.to_linear())
+            }
+        }
+
+        impl LinearRGB<$t> {
+            pub fn new(r: $t,g: $t,b: $t) -> LinearRGB<$t> {
+                LinearRGB(RGB::<$t>::new(r,g,b))
+            }
+
+            pub fn to_srgb(&self) -> SRGB<$t> {
+                SRGB(self.0.to_srgb())
+            }
+        }
+
+        impl LinearRGBA<$t> {
+            pub fn new(r: $t,g: $t,b: $t,a: $t) -> LinearRGBA<$t> {
+                LinearRGBA(RGBA::<$t>::new(r,g,b,a))
+            }
+
+            pub fn to_srgb(&self) -> SRGBA<$t> {
+                SRGBA(self.0.to_srgb())
+            }
+        }
+
+        impl From<SRGB<$t>> for LinearRGB<$t> {
+            fn from(c: SRGB<$t>) -> LinearRGB<$t> {
+                c.to_linear()
+            }
+        }
+
+        impl From<LinearRGB<$t>> for SRGB<$t> {
+            fn from(c: LinearRGB<$t>) -> SRGB<$t> {
+                c.to_srgb()
+            }
+        }
+
+        impl From<SRGBA<$t>> for LinearRGBA<$t> {
+            fn from(c: SRGBA<$t>) -> LinearRGBA<$t> {
+                c.to_linear()
+            }
+        }
+
+        impl From<LinearRGBA<$t>> for SRGBA<$t> {
+            fn from(c: LinearRGBA<$t>) -> SRGBA<$t> {
+                c.to_srgb()
+            }
+        }
+
+        impl_transfer_common!(SRGB,$t);
+        impl_transfer_common!(SRGBA,$t);
+        impl_transfer_common!(LinearRGB,$t);
+        impl_transfer_common!(LinearRGBA,$t);
+        impl_transfer_linear!(LinearRGB,$t);
+        impl_transfer_linear!(LinearRGBA,$t);
+    );
+);
+
+macro_rules! impl_transfer_common (
+    ($w:ident,$t:ty) => (
+        impl cmp::PartialEq for $w<$t> {
+            fn eq(&self,other: &$w<$t>) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl fmt::Display for $w<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"{}",self.0)
+            }
+        }
+
+        impl fmt::Debug for $w<$t> {
+            fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f,"{}({})",stringify!($w),self.0)
+            }
+        }
+
+        impl Zero for $w<$t> {
+            fn zero() -> $w<$t> {
+                $w(Zero::zero())
+            }
+        }
+    );
+);
+
+// only linear light can be added and scaled meaningfully
+macro_rules! impl_transfer_linear (
+    ($w:ident,$t:ty) => (
+        impl ops::Add<$w<$t>> for $w<$t> {
+            type Output = $w<$t>;
+            fn add(self,other: $w<$t>) -> Self::Output {
+                $w(self.0 + other.0)
+            }
+        }
+
+        impl ops::Sub<$w<$t>> for $w<$t> {
+            type Output = $w<$t>;
+            fn sub(self,other: $w<$t>) -> Self::Output {
+                $w(self.0 - other.0)
+            }
+        }
+
+        impl ops::AddAssign<$w<$t>> for $w<$t> {
+            fn add_assign(&mut self,other: $w<$t>) {
+                self.0 += other.0;
+            }
+        }
+
+        impl ops::SubAssign<$w<$t>> for $w<$t> {
+            fn sub_assign(&mut self,other: $w<$t>) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl ops::Mul<$t> for $w<$t> {
+            type Output = $w<$t>;
+            fn mul(self,other: $t) -> Self::Output {
+                $w(self.0 * other)
+            }
+        }
+
+        impl ops::Mul<$w<$t>> for $t {
+            type Output = $w<$t>;
+            fn mul(self,other: $w<$t>) -> Self::Output {
+                $w(self * other.0)
+            }
+        }
+
+        impl ops::Mul<$w<$t>> for $w<$t> {
+            type Output = $w<$t>;
+            fn mul(self,other: $w<$t>) -> Self::Output {
+                $w(self.0 * other.0)
+            }
+        }
+
+        impl ops::MulAssign<$t> for $w<$t> {
+            fn mul_assign(&mut self,other: $t) {
+                self.0 *= other;
+            }
+        }
+
+        impl ops::MulAssign<$w<$t>> for $w<$t> {
+            fn mul_assign(&mut self,other: $w<$t>) {
+                self.0 *= other.0;
+            }
+        }
+
+        impl ops::Div<$t> for $w<$t> {
+            type Output = $w<$t>;
+            fn div(self,other: $t) -> Self::Output {
+                $w(self.0 / other)
+            }
+        }
+
+        impl ops::DivAssign<$t> for $w<$t> {
+            fn div_assign(&mut self,other: $t) {
+                self.0 /= other;
+            }
+        }
+    );
+);
+
+#[allow(non_camel_case_types)]
+pub type f32srgb = SRGB<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32srgba = SRGBA<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32linearrgb = LinearRGB<f32>;
+
+#[allow(non_camel_case_types)]
+pub type f32linearrgba = LinearRGBA<f32>;
+
+impl_transfer!(f32);
+
+#[allow(non_camel_case_types)]
+pub type f64srgb = SRGB<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64srgba = SRGBA<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64linearrgb = LinearRGB<f64>;
+
+#[allow(non_camel_case_types)]
+pub type f64linearrgba = LinearRGBA<f64>;
+
+impl_transfer!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [TransferFunction; 6] = [
+        TransferFunction::Linear,
+        TransferFunction::SRGB,
+        TransferFunction::Rec709,
+        TransferFunction::Rec2020,
+        TransferFunction::BT1886,
+        TransferFunction::DisplayP3,
+    ];
+
+    #[test]
+    fn round_trip() {
+        for &tf in ALL.iter() {
+            for n in -20..=20 {
+                let l = n as f64 / 10.0;
+                let v = f64rgb::encode_component(tf,l);
+                assert!((f64rgb::decode_component(tf,v) - l).abs() < 1e-12,"{:?} {}",tf,l);
+            }
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        // Rec.709 and Rec.2020 are the same OETF
+        for n in 0..=100 {
+            let l = n as f64 / 100.0;
+            assert_eq!(f64rgb::encode_component(TransferFunction::Rec709,l),f64rgb::encode_component(TransferFunction::Rec2020,l));
+        }
+        assert!((f64rgb::encode_component(TransferFunction::Rec709,0.01) - 0.045).abs() < 1e-12);
+        assert!((f64rgb::encode_component(TransferFunction::Rec709,0.18) - 0.408848108891225).abs() < 1e-12);
+        assert!((f64rgb::encode_component(TransferFunction::Rec709,1.0) - 1.0).abs() < 1e-12);
+        // BT.1886 decodes mid grey to 0.5^2.4, the inverse OETF does not
+        assert!((f64rgb::decode_component(TransferFunction::BT1886,0.5) - 0.189464570813800).abs() < 1e-12);
+        assert!((f64rgb::decode_component(TransferFunction::Rec709,0.5) - 0.259719437101178).abs() < 1e-12);
+        assert!((f64rgb::decode_component(TransferFunction::SRGB,0.5) - 0.214041140482233).abs() < 1e-12);
+    }
+}
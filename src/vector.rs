@@ -1,10 +1,138 @@
 // Math
 // by Desmond Germans
 
-use std::{cmp,fmt,ops};
+use std::{cmp,fmt,hash,iter,ops};
 use crate::*;
 
+// Geometry shared by the float vectors, $c lists the components. abs is the
+// modulus and norm scales to unit length, zero vectors are returned unchanged
+// by norm. length and normalize are the same under their usual names.
+macro_rules! impl_vector_float (
+    ($v:ident,$t:ty; $($c:ident),*) => (
+        impl $v<$t> {
+            pub fn dot(a: $v<$t>,b: $v<$t>) -> $t {
+                0.0 $(+ a.$c * b.$c)*
+            }
+
+            pub fn abs(&self) -> $t {
+                self.length_squared().sqrt()
+            }
+
+            pub fn length_squared(self) -> $t {
+                $v::<$t>::dot(self,self)
+            }
+
+            pub fn norm(self) -> $v<$t> {
+                let d = self.abs();
+                if d != 0.0 {
+                    self / d
+                }
+                else {
+                    self
+                }
+            }
+
+            pub fn length(self) -> $t {
+                self.abs()
+            }
+
+            pub fn normalize(self) -> $v<$t> {
+                self.norm()
+            }
+
+            // None for zero, infinite or NaN vectors
+            pub fn try_normalize(self) -> Option<$v<$t>> {
+                let d = self.abs();
+                if (d != 0.0) && d.is_finite() {
+                    Some(self / d)
+                }
+                else {
+                    None
+                }
+            }
+
+            pub fn distance(a: $v<$t>,b: $v<$t>) -> $t {
+                (b - a).abs()
+            }
+
+            pub fn lerp(a: $v<$t>,b: $v<$t>,t: $t) -> $v<$t> {
+                a + (b - a) * t
+            }
+
+            // zero if onto is zero
+            pub fn project_onto(self,onto: $v<$t>) -> $v<$t> {
+                let d = onto.length_squared();
+                if d != 0.0 {
+                    onto * ($v::<$t>::dot(self,onto) / d)
+                }
+                else {
+                    $v::<$t>::zero()
+                }
+            }
+
+            pub fn reject_from(self,from: $v<$t>) -> $v<$t> {
+                self - self.project_onto(from)
+            }
+
+            // normal should be normalized
+            pub fn reflect(self,normal: $v<$t>) -> $v<$t> {
+                self - normal * (2.0 * $v::<$t>::dot(self,normal))
+            }
+
+            // like GLSL refract, self and normal should be normalized, eta is
+            // the ratio of indices of refraction, zero on total internal reflection
+            pub fn refract(self,normal: $v<$t>,eta: $t) -> $v<$t> {
+                let d = $v::<$t>::dot(normal,self);
+                let k = 1.0 - eta * eta * (1.0 - d * d);
+                if k < 0.0 {
+                    $v::<$t>::zero()
+                }
+                else {
+                    self * eta - normal * (eta * d + k.sqrt())
+                }
+            }
+
+            // in radians, zero if either vector is zero
+            pub fn angle_between(a: $v<$t>,b: $v<$t>) -> $t {
+                let d = (a.length_squared() * b.length_squared()).sqrt();
+                if d != 0.0 {
+                    ($v::<$t>::dot(a,b) / d).clamp(-1.0,1.0).acos()
+                }
+                else {
+                    0.0
+                }
+            }
+
+            pub fn floor(self) -> $v<$t> {
+                $v {
+                    $($c: self.$c.floor(),)*
+                }
+            }
+
+            pub fn ceil(self) -> $v<$t> {
+                $v {
+                    $($c: self.$c.ceil(),)*
+                }
+            }
+
+            pub fn round(self) -> $v<$t> {
+                $v {
+                    $($c: self.$c.round(),)*
+                }
+            }
+
+            // like GLSL, x - floor(x), so always in [0,1)
+            pub fn fract(self) -> $v<$t> {
+                $v {
+                    $($c: self.$c - self.$c.floor(),)*
+                }
+            }
+        }
+    );
+);
+
 #[derive(Copy,Clone)]
+#[repr(C)]
 pub struct XY<T> {
     pub x: T,
     pub y: T,
@@ -19,6 +147,40 @@ macro_rules! impl_xy (
                     y: y,
                 }
             }
+
+            pub fn min(self,other: XY<$t>) -> XY<$t> {
+                XY {
+                    x: self.x.min(other.x),
+                    y: self.y.min(other.y),
+                }
+            }
+
+            pub fn max(self,other: XY<$t>) -> XY<$t> {
+                XY {
+                    x: self.x.max(other.x),
+                    y: self.y.max(other.y),
+                }
+            }
+
+            pub fn clamp(self,min: XY<$t>,max: XY<$t>) -> XY<$t> {
+                self.max(min).min(max)
+            }
+
+            pub fn min_element(self) -> $t {
+                self.x.min(self.y)
+            }
+
+            pub fn max_element(self) -> $t {
+                self.x.max(self.y)
+            }
+
+            pub fn sum(self) -> $t {
+                self.x + self.y
+            }
+
+            pub fn product(self) -> $t {
+                self.x * self.y
+            }
         }
 
         impl cmp::PartialEq for XY<$t> {
@@ -126,11 +288,141 @@ macro_rules! impl_xy (
                 self.y /= other;
             }
         }
+
+        impl ops::Mul<XY<$t>> for XY<$t> {
+            type Output = XY<$t>;
+            fn mul(self,other: XY<$t>) -> Self::Output {
+                XY {
+                    x: self.x * other.x,
+                    y: self.y * other.y,
+                }
+            }
+        }
+
+        impl ops::MulAssign<XY<$t>> for XY<$t> {
+            fn mul_assign(&mut self,other: XY<$t>) {
+                self.x *= other.x;
+                self.y *= other.y;
+            }
+        }
+
+        impl ops::Div<XY<$t>> for XY<$t> {
+            type Output = XY<$t>;
+            fn div(self,other: XY<$t>) -> Self::Output {
+                XY {
+                    x: self.x / other.x,
+                    y: self.y / other.y,
+                }
+            }
+        }
+
+        impl ops::DivAssign<XY<$t>> for XY<$t> {
+            fn div_assign(&mut self,other: XY<$t>) {
+                self.x /= other.x;
+                self.y /= other.y;
+            }
+        }
+
+        impl iter::Sum<XY<$t>> for XY<$t> {
+            fn sum<I: Iterator<Item=XY<$t>>>(iter: I) -> XY<$t> {
+                iter.fold(XY::<$t>::zero(),|a,b| a + b)
+            }
+        }
+
+        impl<'a> iter::Sum<&'a XY<$t>> for XY<$t> {
+            fn sum<I: Iterator<Item=&'a XY<$t>>>(iter: I) -> XY<$t> {
+                iter.fold(XY::<$t>::zero(),|a,b| a + *b)
+            }
+        }
+
+        impl iter::Product<XY<$t>> for XY<$t> {
+            fn product<I: Iterator<Item=XY<$t>>>(iter: I) -> XY<$t> {
+                let one = XY {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * b)
+            }
+        }
+
+        impl<'a> iter::Product<&'a XY<$t>> for XY<$t> {
+            fn product<I: Iterator<Item=&'a XY<$t>>>(iter: I) -> XY<$t> {
+                let one = XY {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * *b)
+            }
+        }
+    );
+);
+
+// integer vectors can be map keys and are ordered lexicographically, x first
+macro_rules! impl_xy_integer (
+    ($t:ty) => (
+        impl cmp::Eq for XY<$t> { }
+
+        impl hash::Hash for XY<$t> {
+            fn hash<H: hash::Hasher>(&self,state: &mut H) {
+                self.x.hash(state);
+                self.y.hash(state);
+            }
+        }
+
+        impl cmp::PartialOrd for XY<$t> {
+            fn partial_cmp(&self,other: &XY<$t>) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl cmp::Ord for XY<$t> {
+            fn cmp(&self,other: &XY<$t>) -> cmp::Ordering {
+                self.x.cmp(&other.x)
+                    .then(self.y.cmp(&other.y))
+            }
+        }
+    );
+);
+
+macro_rules! impl_xy_float (
+    ($t:ty) => (
+        impl_vector_float!(XY,$t; x,y);
+
+        impl XY<$t> {
+            // z of the 3D cross product, positive if b is counterclockwise from a
+            pub fn perp_dot(a: XY<$t>,b: XY<$t>) -> $t {
+                a.x * b.y - a.y * b.x
+            }
+
+            // rotated 90 degrees counterclockwise
+            pub fn perp(self) -> XY<$t> {
+                XY {
+                    x: -self.y,
+                    y: self.x,
+                }
+            }
+        }
     );
 );
 
 macro_rules! impl_xy_neg (
     ($t:ty) => (
+        impl XY<$t> {
+            pub fn abs_components(self) -> XY<$t> {
+                XY {
+                    x: self.x.abs(),
+                    y: self.y.abs(),
+                }
+            }
+
+            pub fn signum(self) -> XY<$t> {
+                XY {
+                    x: self.x.signum(),
+                    y: self.y.signum(),
+                }
+            }
+        }
+
         impl ops::Neg for XY<$t> {
             type Output = Self;
             fn neg(self) -> Self::Output {
@@ -147,70 +439,83 @@ macro_rules! impl_xy_neg (
 pub type u8xy = XY<u8>;
 
 impl_xy!(u8);
+impl_xy_integer!(u8);
 
 #[allow(non_camel_case_types)]
 pub type i8xy = XY<i8>;
 
 impl_xy!(i8);
+impl_xy_integer!(i8);
 impl_xy_neg!(i8);
 
 #[allow(non_camel_case_types)]
 pub type u16xy = XY<u16>;
 
 impl_xy!(u16);
+impl_xy_integer!(u16);
 
 #[allow(non_camel_case_types)]
 pub type i16xy = XY<i16>;
 
 impl_xy!(i16);
+impl_xy_integer!(i16);
 impl_xy_neg!(i16);
 
 #[allow(non_camel_case_types)]
 pub type u32xy = XY<u32>;
 
 impl_xy!(u32);
+impl_xy_integer!(u32);
 
 #[allow(non_camel_case_types)]
 pub type i32xy = XY<i32>;
 
 impl_xy!(i32);
+impl_xy_integer!(i32);
 impl_xy_neg!(i32);
 
 #[allow(non_camel_case_types)]
 pub type u64xy = XY<u64>;
 
 impl_xy!(u64);
+impl_xy_integer!(u64);
 
 #[allow(non_camel_case_types)]
 pub type i64xy = XY<i64>;
 
 impl_xy!(i64);
+impl_xy_integer!(i64);
 impl_xy_neg!(i64);
 
 #[allow(non_camel_case_types)]
 pub type usizexy = XY<usize>;
 
 impl_xy!(usize);
+impl_xy_integer!(usize);
 
 #[allow(non_camel_case_types)]
 pub type isizexy = XY<isize>;
 
 impl_xy!(isize);
+impl_xy_integer!(isize);
 impl_xy_neg!(isize);
 
 #[allow(non_camel_case_types)]
 pub type f32xy = XY<f32>;
 
 impl_xy!(f32);
+impl_xy_float!(f32);
 impl_xy_neg!(f32);
 
 #[allow(non_camel_case_types)]
 pub type f64xy = XY<f64>;
 
 impl_xy!(f64);
+impl_xy_float!(f64);
 impl_xy_neg!(f64);
 
 #[derive(Copy,Clone)]
+#[repr(C)]
 pub struct XYZ<T> {
     pub x: T,
     pub y: T,
@@ -228,30 +533,40 @@ macro_rules! impl_xyz (
                 }
             }
 
-            pub fn cross(a: XYZ<$t>,b: XYZ<$t>) -> XYZ<$t> {
+            pub fn min(self,other: XYZ<$t>) -> XYZ<$t> {
                 XYZ {
-                    x: a.y * b.z - a.z * b.y,
-                    y: a.z * b.x - a.x * b.z,
-                    z: a.x * b.y - a.y * b.x,
+                    x: self.x.min(other.x),
+                    y: self.y.min(other.y),
+                    z: self.z.min(other.z),
                 }
             }
 
-            pub fn dot(a: XYZ<$t>,b: XYZ<$t>) -> $t {
-                a.x * b.x + a.y * b.y + a.z * b.z
+            pub fn max(self,other: XYZ<$t>) -> XYZ<$t> {
+                XYZ {
+                    x: self.x.max(other.x),
+                    y: self.y.max(other.y),
+                    z: self.z.max(other.z),
+                }
             }
 
-            pub fn abs(&self) -> $t {
-                (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+            pub fn clamp(self,min: XYZ<$t>,max: XYZ<$t>) -> XYZ<$t> {
+                self.max(min).min(max)
             }
 
-            pub fn norm(self) -> XYZ<$t> {
-                let d = self.abs();
-                if d != 0.0 {
-                    self / d
-                }
-                else {
-                    self
-                }
+            pub fn min_element(self) -> $t {
+                self.x.min(self.y).min(self.z)
+            }
+
+            pub fn max_element(self) -> $t {
+                self.x.max(self.y).max(self.z)
+            }
+
+            pub fn sum(self) -> $t {
+                self.x + self.y + self.z
+            }
+
+            pub fn product(self) -> $t {
+                self.x * self.y * self.z
             }
         }
 
@@ -285,17 +600,6 @@ macro_rules! impl_xyz (
             }
         }
 
-        impl ops::Neg for XYZ<$t> {
-            type Output = XYZ<$t>;
-            fn neg(self) -> Self::Output {
-                XYZ {
-                    x: -self.x,
-                    y: -self.y,
-                    z: -self.z,
-                }
-            }
-        }
-
         impl ops::Add<XYZ<$t>> for XYZ<$t> {
             type Output = XYZ<$t>;
             fn add(self,other: XYZ<$t>) -> Self::Output {
@@ -365,6 +669,177 @@ macro_rules! impl_xyz (
             }
         }
 
+        impl ops::Mul<XYZ<$t>> for XYZ<$t> {
+            type Output = XYZ<$t>;
+            fn mul(self,other: XYZ<$t>) -> Self::Output {
+                XYZ {
+                    x: self.x * other.x,
+                    y: self.y * other.y,
+                    z: self.z * other.z,
+                }
+            }
+        }
+
+        impl ops::MulAssign<XYZ<$t>> for XYZ<$t> {
+            fn mul_assign(&mut self,other: XYZ<$t>) {
+                self.x *= other.x;
+                self.y *= other.y;
+                self.z *= other.z;
+            }
+        }
+
+        impl iter::Sum<XYZ<$t>> for XYZ<$t> {
+            fn sum<I: Iterator<Item=XYZ<$t>>>(iter: I) -> XYZ<$t> {
+                iter.fold(XYZ::<$t>::zero(),|a,b| a + b)
+            }
+        }
+
+        impl<'a> iter::Sum<&'a XYZ<$t>> for XYZ<$t> {
+            fn sum<I: Iterator<Item=&'a XYZ<$t>>>(iter: I) -> XYZ<$t> {
+                iter.fold(XYZ::<$t>::zero(),|a,b| a + *b)
+            }
+        }
+
+        impl iter::Product<XYZ<$t>> for XYZ<$t> {
+            fn product<I: Iterator<Item=XYZ<$t>>>(iter: I) -> XYZ<$t> {
+                let one = XYZ {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                    z: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * b)
+            }
+        }
+
+        impl<'a> iter::Product<&'a XYZ<$t>> for XYZ<$t> {
+            fn product<I: Iterator<Item=&'a XYZ<$t>>>(iter: I) -> XYZ<$t> {
+                let one = XYZ {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                    z: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * *b)
+            }
+        }
+    );
+);
+
+macro_rules! impl_xyz_neg (
+    ($t:ty) => (
+        impl XYZ<$t> {
+            pub fn abs_components(self) -> XYZ<$t> {
+                XYZ {
+                    x: self.x.abs(),
+                    y: self.y.abs(),
+                    z: self.z.abs(),
+                }
+            }
+
+            pub fn signum(self) -> XYZ<$t> {
+                XYZ {
+                    x: self.x.signum(),
+                    y: self.y.signum(),
+                    z: self.z.signum(),
+                }
+            }
+        }
+
+        impl ops::Neg for XYZ<$t> {
+            type Output = XYZ<$t>;
+            fn neg(self) -> Self::Output {
+                XYZ {
+                    x: -self.x,
+                    y: -self.y,
+                    z: -self.z,
+                }
+            }
+        }
+    );
+);
+
+// integer division truncates and panics on 0, integer vectors can be map keys
+// and are ordered lexicographically, x first (the inherent min and max stay
+// component-wise, they take precedence over Ord::min and Ord::max)
+macro_rules! impl_xyz_integer (
+    ($t:ty) => (
+        impl ops::Div<$t> for XYZ<$t> {
+            type Output = XYZ<$t>;
+            fn div(self,other: $t) -> Self::Output {
+                XYZ {
+                    x: self.x / other,
+                    y: self.y / other,
+                    z: self.z / other,
+                }
+            }
+        }
+
+        impl ops::DivAssign<$t> for XYZ<$t> {
+            fn div_assign(&mut self,other: $t) {
+                self.x /= other;
+                self.y /= other;
+                self.z /= other;
+            }
+        }
+
+        impl cmp::Eq for XYZ<$t> { }
+
+        impl hash::Hash for XYZ<$t> {
+            fn hash<H: hash::Hasher>(&self,state: &mut H) {
+                self.x.hash(state);
+                self.y.hash(state);
+                self.z.hash(state);
+            }
+        }
+
+        impl cmp::PartialOrd for XYZ<$t> {
+            fn partial_cmp(&self,other: &XYZ<$t>) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl cmp::Ord for XYZ<$t> {
+            fn cmp(&self,other: &XYZ<$t>) -> cmp::Ordering {
+                self.x.cmp(&other.x)
+                    .then(self.y.cmp(&other.y))
+                    .then(self.z.cmp(&other.z))
+            }
+        }
+
+        impl ops::Div<XYZ<$t>> for XYZ<$t> {
+            type Output = XYZ<$t>;
+            fn div(self,other: XYZ<$t>) -> Self::Output {
+                XYZ {
+                    x: self.x / other.x,
+                    y: self.y / other.y,
+                    z: self.z / other.z,
+                }
+            }
+        }
+
+        impl ops::DivAssign<XYZ<$t>> for XYZ<$t> {
+            fn div_assign(&mut self,other: XYZ<$t>) {
+                self.x /= other.x;
+                self.y /= other.y;
+                self.z /= other.z;
+            }
+        }
+    );
+);
+
+macro_rules! impl_xyz_float (
+    ($t:ty) => (
+        impl_vector_float!(XYZ,$t; x,y,z);
+
+        impl XYZ<$t> {
+            pub fn cross(a: XYZ<$t>,b: XYZ<$t>) -> XYZ<$t> {
+                XYZ {
+                    x: a.y * b.z - a.z * b.y,
+                    y: a.z * b.x - a.x * b.z,
+                    z: a.x * b.y - a.y * b.x,
+                }
+            }
+        }
+
         impl ops::Div<$t> for XYZ<$t> {
             type Output = XYZ<$t>;
             fn div(self,other: $t) -> Self::Output {
@@ -390,20 +865,113 @@ macro_rules! impl_xyz (
                 }
             }
         }
+
+        impl ops::Div<XYZ<$t>> for XYZ<$t> {
+            type Output = XYZ<$t>;
+            fn div(self,other: XYZ<$t>) -> Self::Output {
+                let mut r = self;
+                r /= other;
+                r
+            }
+        }
+
+        impl ops::DivAssign<XYZ<$t>> for XYZ<$t> {
+            fn div_assign(&mut self,other: XYZ<$t>) {
+                if other.x != 0.0 {
+                    self.x /= other.x;
+                }
+                if other.y != 0.0 {
+                    self.y /= other.y;
+                }
+                if other.z != 0.0 {
+                    self.z /= other.z;
+                }
+            }
+        }
     );
 );
 
+#[allow(non_camel_case_types)]
+pub type u8xyz = XYZ<u8>;
+
+impl_xyz!(u8);
+impl_xyz_integer!(u8);
+
+#[allow(non_camel_case_types)]
+pub type i8xyz = XYZ<i8>;
+
+impl_xyz!(i8);
+impl_xyz_integer!(i8);
+impl_xyz_neg!(i8);
+
+#[allow(non_camel_case_types)]
+pub type u16xyz = XYZ<u16>;
+
+impl_xyz!(u16);
+impl_xyz_integer!(u16);
+
+#[allow(non_camel_case_types)]
+pub type i16xyz = XYZ<i16>;
+
+impl_xyz!(i16);
+impl_xyz_integer!(i16);
+impl_xyz_neg!(i16);
+
+#[allow(non_camel_case_types)]
+pub type u32xyz = XYZ<u32>;
+
+impl_xyz!(u32);
+impl_xyz_integer!(u32);
+
+#[allow(non_camel_case_types)]
+pub type i32xyz = XYZ<i32>;
+
+impl_xyz!(i32);
+impl_xyz_integer!(i32);
+impl_xyz_neg!(i32);
+
+#[allow(non_camel_case_types)]
+pub type u64xyz = XYZ<u64>;
+
+impl_xyz!(u64);
+impl_xyz_integer!(u64);
+
+#[allow(non_camel_case_types)]
+pub type i64xyz = XYZ<i64>;
+
+impl_xyz!(i64);
+impl_xyz_integer!(i64);
+impl_xyz_neg!(i64);
+
+#[allow(non_camel_case_types)]
+pub type usizexyz = XYZ<usize>;
+
+impl_xyz!(usize);
+impl_xyz_integer!(usize);
+
+#[allow(non_camel_case_types)]
+pub type isizexyz = XYZ<isize>;
+
+impl_xyz!(isize);
+impl_xyz_integer!(isize);
+impl_xyz_neg!(isize);
+
 #[allow(non_camel_case_types)]
 pub type f32xyz = XYZ<f32>;
 
 impl_xyz!(f32);
+impl_xyz_float!(f32);
+impl_xyz_neg!(f32);
 
 #[allow(non_camel_case_types)]
 pub type f64xyz = XYZ<f64>;
 
 impl_xyz!(f64);
+impl_xyz_float!(f64);
+impl_xyz_neg!(f64);
 
 #[derive(Copy,Clone)]
+#[repr(C)]
 pub struct XYZW<T> {
     pub x: T,
     pub y: T,
@@ -422,6 +990,44 @@ macro_rules! impl_xyzw (
                     w: w,
                 }
             }
+
+            pub fn min(self,other: XYZW<$t>) -> XYZW<$t> {
+                XYZW {
+                    x: self.x.min(other.x),
+                    y: self.y.min(other.y),
+                    z: self.z.min(other.z),
+                    w: self.w.min(other.w),
+                }
+            }
+
+            pub fn max(self,other: XYZW<$t>) -> XYZW<$t> {
+                XYZW {
+                    x: self.x.max(other.x),
+                    y: self.y.max(other.y),
+                    z: self.z.max(other.z),
+                    w: self.w.max(other.w),
+                }
+            }
+
+            pub fn clamp(self,min: XYZW<$t>,max: XYZW<$t>) -> XYZW<$t> {
+                self.max(min).min(max)
+            }
+
+            pub fn min_element(self) -> $t {
+                self.x.min(self.y).min(self.z).min(self.w)
+            }
+
+            pub fn max_element(self) -> $t {
+                self.x.max(self.y).max(self.z).max(self.w)
+            }
+
+            pub fn sum(self) -> $t {
+                self.x + self.y + self.z + self.w
+            }
+
+            pub fn product(self) -> $t {
+                self.x * self.y * self.z * self.w
+            }
         }
 
         impl cmp::PartialEq for XYZW<$t> {
@@ -456,18 +1062,6 @@ macro_rules! impl_xyzw (
             }
         }
 
-        impl ops::Neg for XYZW<$t> {
-            type Output = XYZW<$t>;
-            fn neg(self) -> Self::Output {
-                XYZW {
-                    x: -self.x,
-                    y: -self.y,
-                    z: -self.z,
-                    w: -self.w,
-                }
-            }
-        }
-
         impl ops::Add<XYZW<$t>> for XYZW<$t> {
             type Output = XYZW<$t>;
             fn add(self,other: XYZW<$t>) -> Self::Output {
@@ -543,6 +1137,193 @@ macro_rules! impl_xyzw (
             }
         }
 
+        impl ops::Mul<XYZW<$t>> for XYZW<$t> {
+            type Output = XYZW<$t>;
+            fn mul(self,other: XYZW<$t>) -> Self::Output {
+                XYZW {
+                    x: self.x * other.x,
+                    y: self.y * other.y,
+                    z: self.z * other.z,
+                    w: self.w * other.w,
+                }
+            }
+        }
+
+        impl ops::MulAssign<XYZW<$t>> for XYZW<$t> {
+            fn mul_assign(&mut self,other: XYZW<$t>) {
+                self.x *= other.x;
+                self.y *= other.y;
+                self.z *= other.z;
+                self.w *= other.w;
+            }
+        }
+
+        impl iter::Sum<XYZW<$t>> for XYZW<$t> {
+            fn sum<I: Iterator<Item=XYZW<$t>>>(iter: I) -> XYZW<$t> {
+                iter.fold(XYZW::<$t>::zero(),|a,b| a + b)
+            }
+        }
+
+        impl<'a> iter::Sum<&'a XYZW<$t>> for XYZW<$t> {
+            fn sum<I: Iterator<Item=&'a XYZW<$t>>>(iter: I) -> XYZW<$t> {
+                iter.fold(XYZW::<$t>::zero(),|a,b| a + *b)
+            }
+        }
+
+        impl iter::Product<XYZW<$t>> for XYZW<$t> {
+            fn product<I: Iterator<Item=XYZW<$t>>>(iter: I) -> XYZW<$t> {
+                let one = XYZW {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                    z: <$t>::one(),
+                    w: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * b)
+            }
+        }
+
+        impl<'a> iter::Product<&'a XYZW<$t>> for XYZW<$t> {
+            fn product<I: Iterator<Item=&'a XYZW<$t>>>(iter: I) -> XYZW<$t> {
+                let one = XYZW {
+                    x: <$t>::one(),
+                    y: <$t>::one(),
+                    z: <$t>::one(),
+                    w: <$t>::one(),
+                };
+                iter.fold(one,|a,b| a * *b)
+            }
+        }
+    );
+);
+
+macro_rules! impl_xyzw_neg (
+    ($t:ty) => (
+        impl XYZW<$t> {
+            pub fn abs_components(self) -> XYZW<$t> {
+                XYZW {
+                    x: self.x.abs(),
+                    y: self.y.abs(),
+                    z: self.z.abs(),
+                    w: self.w.abs(),
+                }
+            }
+
+            pub fn signum(self) -> XYZW<$t> {
+                XYZW {
+                    x: self.x.signum(),
+                    y: self.y.signum(),
+                    z: self.z.signum(),
+                    w: self.w.signum(),
+                }
+            }
+        }
+
+        impl ops::Neg for XYZW<$t> {
+            type Output = XYZW<$t>;
+            fn neg(self) -> Self::Output {
+                XYZW {
+                    x: -self.x,
+                    y: -self.y,
+                    z: -self.z,
+                    w: -self.w,
+                }
+            }
+        }
+    );
+);
+
+macro_rules! impl_xyzw_integer (
+    ($t:ty) => (
+        impl ops::Div<$t> for XYZW<$t> {
+            type Output = XYZW<$t>;
+            fn div(self,other: $t) -> Self::Output {
+                XYZW {
+                    x: self.x / other,
+                    y: self.y / other,
+                    z: self.z / other,
+                    w: self.w / other,
+                }
+            }
+        }
+
+        impl ops::DivAssign<$t> for XYZW<$t> {
+            fn div_assign(&mut self,other: $t) {
+                self.x /= other;
+                self.y /= other;
+                self.z /= other;
+                self.w /= other;
+            }
+        }
+
+        impl cmp::Eq for XYZW<$t> { }
+
+        impl hash::Hash for XYZW<$t> {
+            fn hash<H: hash::Hasher>(&self,state: &mut H) {
+                self.x.hash(state);
+                self.y.hash(state);
+                self.z.hash(state);
+                self.w.hash(state);
+            }
+        }
+
+        impl cmp::PartialOrd for XYZW<$t> {
+            fn partial_cmp(&self,other: &XYZW<$t>) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl cmp::Ord for XYZW<$t> {
+            fn cmp(&self,other: &XYZW<$t>) -> cmp::Ordering {
+                self.x.cmp(&other.x)
+                    .then(self.y.cmp(&other.y))
+                    .then(self.z.cmp(&other.z))
+                    .then(self.w.cmp(&other.w))
+            }
+        }
+
+        impl ops::Div<XYZW<$t>> for XYZW<$t> {
+            type Output = XYZW<$t>;
+            fn div(self,other: XYZW<$t>) -> Self::Output {
+                XYZW {
+                    x: self.x / other.x,
+                    y: self.y / other.y,
+                    z: self.z / other.z,
+                    w: self.w / other.w,
+                }
+            }
+        }
+
+        impl ops::DivAssign<XYZW<$t>> for XYZW<$t> {
+            fn div_assign(&mut self,other: XYZW<$t>) {
+                self.x /= other.x;
+                self.y /= other.y;
+                self.z /= other.z;
+                self.w /= other.w;
+            }
+        }
+    );
+);
+
+macro_rules! impl_xyzw_float (
+    ($t:ty) => (
+        impl_vector_float!(XYZW,$t; x,y,z,w);
+
+        impl XYZW<$t> {
+            // homogeneous divide, w = 0 gives the direction unchanged
+            pub fn dehomogenize(self) -> XYZ<$t> {
+                if self.w != 0.0 {
+                    XYZ {
+                        x: self.x / self.w,
+                        y: self.y / self.w,
+                        z: self.z / self.w,
+                    }
+                }
+                else {
+                    self.xyz()
+                }
+            }
+        }
+
         impl ops::Div<$t> for XYZW<$t> {
             type Output = XYZW<$t>;
             fn div(self,other: $t) -> Self::Output {
@@ -570,15 +1351,185 @@ macro_rules! impl_xyzw (
                 }
             }
         }
+
+        impl ops::Div<XYZW<$t>> for XYZW<$t> {
+            type Output = XYZW<$t>;
+            fn div(self,other: XYZW<$t>) -> Self::Output {
+                let mut r = self;
+                r /= other;
+                r
+            }
+        }
+
+        impl ops::DivAssign<XYZW<$t>> for XYZW<$t> {
+            fn div_assign(&mut self,other: XYZW<$t>) {
+                if other.x != 0.0 {
+                    self.x /= other.x;
+                }
+                if other.y != 0.0 {
+                    self.y /= other.y;
+                }
+                if other.z != 0.0 {
+                    self.z /= other.z;
+                }
+                if other.w != 0.0 {
+                    self.w /= other.w;
+                }
+            }
+        }
     );
 );
 
+#[allow(non_camel_case_types)]
+pub type u8xyzw = XYZW<u8>;
+
+impl_xyzw!(u8);
+impl_xyzw_integer!(u8);
+
+#[allow(non_camel_case_types)]
+pub type i8xyzw = XYZW<i8>;
+
+impl_xyzw!(i8);
+impl_xyzw_integer!(i8);
+impl_xyzw_neg!(i8);
+
+#[allow(non_camel_case_types)]
+pub type u16xyzw = XYZW<u16>;
+
+impl_xyzw!(u16);
+impl_xyzw_integer!(u16);
+
+#[allow(non_camel_case_types)]
+pub type i16xyzw = XYZW<i16>;
+
+impl_xyzw!(i16);
+impl_xyzw_integer!(i16);
+impl_xyzw_neg!(i16);
+
+#[allow(non_camel_case_types)]
+pub type u32xyzw = XYZW<u32>;
+
+impl_xyzw!(u32);
+impl_xyzw_integer!(u32);
+
+#[allow(non_camel_case_types)]
+pub type i32xyzw = XYZW<i32>;
+
+impl_xyzw!(i32);
+impl_xyzw_integer!(i32);
+impl_xyzw_neg!(i32);
+
+#[allow(non_camel_case_types)]
+pub type u64xyzw = XYZW<u64>;
+
+impl_xyzw!(u64);
+impl_xyzw_integer!(u64);
+
+#[allow(non_camel_case_types)]
+pub type i64xyzw = XYZW<i64>;
+
+impl_xyzw!(i64);
+impl_xyzw_integer!(i64);
+impl_xyzw_neg!(i64);
+
+#[allow(non_camel_case_types)]
+pub type usizexyzw = XYZW<usize>;
+
+impl_xyzw!(usize);
+impl_xyzw_integer!(usize);
+
+#[allow(non_camel_case_types)]
+pub type isizexyzw = XYZW<isize>;
+
+impl_xyzw!(isize);
+impl_xyzw_integer!(isize);
+impl_xyzw_neg!(isize);
+
 #[allow(non_camel_case_types)]
 pub type f32xyzw = XYZW<f32>;
 
 impl_xyzw!(f32);
+impl_xyzw_float!(f32);
+impl_xyzw_neg!(f32);
 
 #[allow(non_camel_case_types)]
 pub type f64xyzw = XYZW<f64>;
 
 impl_xyzw!(f64);
+impl_xyzw_float!(f64);
+impl_xyzw_neg!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abs_is_modulus() {
+        let v = f64xyz::new(3.0,-4.0,12.0);
+        assert_eq!(v.abs(),13.0);
+        assert_eq!(v.norm().abs(),1.0);
+        assert_eq!(v.abs_components(),f64xyz::new(3.0,4.0,12.0));
+        assert_eq!(i32xyz::new(-1,2,-3).abs_components(),i32xyz::new(1,2,3));
+        assert_eq!(f32xy::new(-1.5,2.0).abs_components(),f32xy::new(1.5,2.0));
+        assert_eq!(i8xyzw::new(-1,0,1,-128).signum(),i8xyzw::new(-1,0,1,-1));
+    }
+
+    #[test]
+    fn shared_geometry() {
+        assert_eq!(f32xy::new(3.0,4.0).abs(),5.0);
+        assert_eq!(f64xyzw::new(1.0,1.0,1.0,1.0).abs(),2.0);
+        assert_eq!(f64xyzw::new(0.0,0.0,0.0,2.0).norm(),f64xyzw::new(0.0,0.0,0.0,1.0));
+        assert_eq!(f32xy::new(0.0,0.0).norm(),f32xy::new(0.0,0.0));
+        assert_eq!(f64xyz::new(0.0,3.0,4.0).length(),5.0);
+        assert_eq!(f32xy::new(0.0,-2.0).normalize(),f32xy::new(0.0,-1.0));
+        assert_eq!(f64xyzw::new(2.0,0.0,0.0,0.0).try_normalize(),Some(f64xyzw::new(1.0,0.0,0.0,0.0)));
+        assert_eq!(f64xyz::new(0.0,0.0,0.0).try_normalize(),None);
+        assert_eq!(f64xyz::new(f64::INFINITY,0.0,0.0).try_normalize(),None);
+        assert_eq!(f64xyz::distance(f64xyz::new(1.0,2.0,3.0),f64xyz::new(4.0,6.0,3.0)),5.0);
+        assert_eq!(f64xy::dot(f64xy::new(1.0,2.0),f64xy::new(3.0,-4.0)),-5.0);
+        assert_eq!(f64xyzw::new(1.5,-1.5,2.7,-0.2).floor(),f64xyzw::new(1.0,-2.0,2.0,-1.0));
+        assert_eq!(f64xyz::new(1.0,1.0,0.0).reject_from(f64xyz::new(2.0,0.0,0.0)),f64xyz::new(0.0,1.0,0.0));
+    }
+
+    #[test]
+    fn integer_ordering() {
+        let mut v = vec![i32xyz::new(1,2,3),i32xyz::new(0,5,5),i32xyz::new(1,2,-1),i32xyz::new(1,-4,9)];
+        v.sort();
+        assert_eq!(v,vec![i32xyz::new(0,5,5),i32xyz::new(1,-4,9),i32xyz::new(1,2,-1),i32xyz::new(1,2,3)]);
+        assert!(u32xyzw::new(0,0,0,1) < u32xyzw::new(0,0,1,0));
+        assert!(u32xyzw::new(1,0,0,0) > u32xyzw::new(0,9,9,9));
+        assert_eq!(u32xyzw::new(1,2,3,4).cmp(&u32xyzw::new(1,2,3,4)),cmp::Ordering::Equal);
+        // the inherent min and max are component-wise, Ord::min picks a whole vector
+        let a = i32xyz::new(1,5,-2);
+        let b = i32xyz::new(3,-1,-2);
+        assert_eq!(a.min(b),i32xyz::new(1,-1,-2));
+        assert_eq!(a.max(b),i32xyz::new(3,5,-2));
+        assert_eq!(cmp::Ord::min(a,b),a);
+        assert_eq!(cmp::Ord::max(a,b),b);
+        let a = u8xyzw::new(1,200,3,0);
+        let b = u8xyzw::new(4,5,3,255);
+        assert_eq!(a.min(b),u8xyzw::new(1,5,3,0));
+        assert_eq!(a.max(b),u8xyzw::new(4,200,3,255));
+    }
+
+    #[test]
+    fn integer_hash() {
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(i16xyz::new(1,2,3)));
+        assert!(set.insert(i16xyz::new(3,2,1)));
+        assert!(!set.insert(i16xyz::new(1,2,3)));
+        assert!(set.contains(&i16xyz::new(3,2,1)));
+        assert!(!set.contains(&i16xyz::new(1,2,4)));
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(u32xyzw::new(1,2,3,4)));
+        assert!(!set.insert(u32xyzw::new(1,2,3,4)));
+        assert!(set.insert(u32xyzw::new(1,2,4,3)));
+        assert_eq!(set.len(),2);
+    }
+
+    #[test]
+    fn integer_neg() {
+        assert_eq!(-i32xyz::new(1,-2,0),i32xyz::new(-1,2,0));
+        assert_eq!(-i8xyzw::new(1,-2,0,127),i8xyzw::new(-1,2,0,-127));
+    }
+}
@@ -0,0 +1,41 @@
+// (C) Copyright 2020, by Germans Media Technology & Services
+// Math
+// Round trip through the image crate
+
+#![cfg(feature = "image")]
+
+use std::io::Cursor;
+use image::{ImageBuffer,ImageFormat,Pixel};
+use math::*;
+
+fn encode_decode(buffer: &image::DynamicImage) -> image::DynamicImage {
+    let mut png = Vec::new();
+    buffer.write_to(&mut Cursor::new(&mut png),ImageFormat::Png).unwrap();
+    image::load_from_memory_with_format(&png,ImageFormat::Png).unwrap()
+}
+
+#[test]
+fn png_round_trip_u8() {
+    let pixels: Vec<u8rgba> = (0..16 * 8).map(|i| u8rgba::new((i * 2) as u8,(255 - i) as u8,(i * 7) as u8,(i * 13) as u8)).collect();
+    let buffer = u8rgba::to_image_buffer(16,8,&pixels).unwrap();
+    let decoded = encode_decode(&image::DynamicImage::ImageRgba8(buffer)).to_rgba8();
+    assert_eq!(u8rgba::from_image_buffer(&decoded),pixels);
+}
+
+#[test]
+fn png_round_trip_u16() {
+    let pixels: Vec<u16rgba> = (0..5 * 3).map(|i| u16rgba::new((i * 4000) as u16,(65535 - i * 3) as u16,(i * 17) as u16,65535)).collect();
+    let buffer = u16rgba::to_image_buffer(5,3,&pixels).unwrap();
+    let decoded = encode_decode(&image::DynamicImage::ImageRgba16(buffer)).to_rgba16();
+    assert_eq!(u16rgba::from_image_buffer(&decoded),pixels);
+}
+
+#[test]
+fn buffer_of_math_pixels() {
+    let data: Vec<u8> = (0..2 * 2 * 3).map(|i| i as u8).collect();
+    let buffer: ImageBuffer<u8rgb,Vec<u8>> = ImageBuffer::from_raw(2,2,data).unwrap();
+    assert_eq!(*buffer.get_pixel(1,1),u8rgb::new(9,10,11));
+    assert_eq!(buffer.get_pixel(1,0).channels(),&[3,4,5]);
+    assert_eq!(u8rgba::from_image_buffer(&buffer)[2],u8rgba::new(6,7,8,255));
+    assert!(u8rgba::to_image_buffer(3,3,&[u8rgba::zero()]).is_none());
+}